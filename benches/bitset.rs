@@ -0,0 +1,104 @@
+//! Day 6 part 1 with the lights in a `HashMap`, a `Grid<bool>` and a `BitSet`.
+//!
+//! Memory for the million lights, roughly: the `HashMap` with `(usize, usize)` keys needs
+//! 2^21 buckets of 17 bytes plus a control byte, about 38MB; the `Grid<bool>` a byte per
+//! light, 1MB; and the `BitSet` a bit per light, 125KB.
+
+#![warn(clippy::pedantic)]
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use aoc2015::bitset::{BitSet, Bits};
+use aoc2015::geometry::{Point, Rect};
+use aoc2015::grid::Grid;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const DAY06_INPUT: &str = include_str!("../src/inputs/day06.txt");
+const SIZE: usize = 1000;
+
+#[derive(Clone, Copy)]
+enum Action {
+    On,
+    Off,
+    Toggle,
+}
+
+fn instructions() -> Vec<(Action, Rect<usize>)> {
+    DAY06_INPUT
+        .trim()
+        .lines()
+        .map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (action, from, to) = match parts.as_slice() {
+                ["turn", "on", from, "through", to] => (Action::On, from, to),
+                ["turn", "off", from, "through", to] => (Action::Off, from, to),
+                ["toggle", from, "through", to] => (Action::Toggle, from, to),
+                _ => panic!("Invalid instruction {line:?}"),
+            };
+            let from: Point<usize> = from.parse().unwrap();
+            let to: Point<usize> = to.parse().unwrap();
+            (action, Rect::new(from, to))
+        })
+        .collect()
+}
+
+fn hashmap(instructions: &[(Action, Rect<usize>)]) -> usize {
+    let mut lights: HashMap<(usize, usize), bool> = HashMap::new();
+    for &(action, rect) in instructions {
+        for point in rect.points() {
+            let light = lights.entry((point.x, point.y)).or_default();
+            *light = match action {
+                Action::On => true,
+                Action::Off => false,
+                Action::Toggle => !*light,
+            };
+        }
+    }
+    lights.values().filter(|&&lit| lit).count()
+}
+
+fn grid(instructions: &[(Action, Rect<usize>)]) -> usize {
+    let mut lights = Grid::new(SIZE, SIZE, false);
+    for &(action, rect) in instructions {
+        for light in lights.view_mut(rect).iter_mut() {
+            *light = match action {
+                Action::On => true,
+                Action::Off => false,
+                Action::Toggle => !*light,
+            };
+        }
+    }
+    lights.iter().filter(|&&lit| lit).count()
+}
+
+fn bitset(instructions: &[(Action, Rect<usize>)]) -> usize {
+    let mut lights = BitSet::with_capacity(SIZE * SIZE);
+    for &(action, rect) in instructions {
+        for y in rect.min.y..=rect.max.y {
+            let row = y * SIZE + rect.min.x..=y * SIZE + rect.max.x;
+            match action {
+                Action::On => lights.insert_range(row),
+                Action::Off => lights.remove_range(row),
+                Action::Toggle => lights.toggle_range(row),
+            }
+        }
+    }
+    lights.count()
+}
+
+fn day06_part1(c: &mut Criterion) {
+    let instructions = instructions();
+    assert_eq!(hashmap(&instructions), bitset(&instructions));
+    assert_eq!(grid(&instructions), bitset(&instructions));
+
+    let mut group = c.benchmark_group("day06 part 1");
+    group.sample_size(10);
+    group.bench_function("hashmap", |b| b.iter(|| hashmap(black_box(&instructions))));
+    group.bench_function("grid", |b| b.iter(|| grid(black_box(&instructions))));
+    group.bench_function("bitset", |b| b.iter(|| bitset(black_box(&instructions))));
+    group.finish();
+}
+
+criterion_group!(benches, day06_part1);
+criterion_main!(benches);
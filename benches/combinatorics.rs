@@ -0,0 +1,189 @@
+//! The combinatorics module against the itertools equivalents it replaces.
+
+#![warn(clippy::pedantic)]
+
+use std::convert::Infallible;
+use std::hint::black_box;
+
+use aoc2015::combinatorics::{
+    for_each_combination, for_each_permutation, members, subsets, subsets_of_size,
+    try_for_each_cycle, try_for_each_path,
+};
+use aoc2015::graph::{Graph, NodeId};
+use criterion::{criterion_group, criterion_main, Criterion};
+use itertools::Itertools;
+
+const DAY09_INPUT: &str = include_str!("../src/inputs/day09.txt");
+
+fn day09_distances() -> Graph<&'static str, u32> {
+    let mut distances = Graph::undirected();
+    for line in DAY09_INPUT.trim().lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if let [from, "to", to, "=", distance] = parts.as_slice() {
+            distances.add_edge(*from, *to, distance.parse().unwrap());
+        }
+    }
+    distances
+}
+
+fn permutations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("permutations of 8");
+
+    group.bench_function("heap", |b| {
+        let mut items: Vec<usize> = (0..8).collect();
+        b.iter(|| {
+            let mut total = 0;
+            for_each_permutation(&mut items, |p| total += p[0]);
+            black_box(total)
+        });
+    });
+
+    group.bench_function("itertools", |b| {
+        let items: Vec<usize> = (0..8).collect();
+        b.iter(|| {
+            let mut total = 0;
+            for p in items.iter().permutations(items.len()) {
+                total += p[0];
+            }
+            black_box(total)
+        });
+    });
+
+    group.finish();
+}
+
+fn day09_routes(c: &mut Criterion) {
+    let distances = day09_distances();
+    let mut group = c.benchmark_group("day09 shortest route");
+
+    group.bench_function("path without reversals", |b| {
+        let mut locations: Vec<NodeId> = distances.nodes().collect();
+        b.iter(|| {
+            let mut shortest = u32::MAX;
+            let Ok(()) = try_for_each_path(&mut locations, |route| {
+                shortest = shortest.min(distances.path_weight(route).unwrap());
+                Ok::<(), Infallible>(())
+            });
+            black_box(shortest)
+        });
+    });
+
+    group.bench_function("itertools unique permutations", |b| {
+        let locations: Vec<NodeId> = distances.nodes().collect();
+        b.iter(|| {
+            let mut shortest = u32::MAX;
+            for route in locations
+                .iter()
+                .copied()
+                .permutations(locations.len())
+                .unique()
+            {
+                shortest = shortest.min(distances.path_weight(&route).unwrap());
+            }
+            black_box(shortest)
+        });
+    });
+
+    group.finish();
+}
+
+fn cycles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round trips of 9");
+
+    group.bench_function("cycles without rotations or reflections", |b| {
+        let mut items: Vec<usize> = (0..9).collect();
+        b.iter(|| {
+            let mut total = 0;
+            let Ok(()) = try_for_each_cycle(&mut items, |cycle| {
+                total += cycle[1];
+                Ok::<(), Infallible>(())
+            });
+            black_box(total)
+        });
+    });
+
+    group.bench_function("itertools", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            // 0 fixed as the start, and of each mirror image pair the one going up first
+            for rest in (1..9).permutations(8) {
+                if rest[0] < rest[7] {
+                    total += rest[0];
+                }
+            }
+            black_box(total)
+        });
+    });
+
+    group.finish();
+}
+
+fn combinations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("20 choose 5");
+
+    group.bench_function("indices", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for_each_combination(20, 5, |indices| total += indices[4]);
+            black_box(total)
+        });
+    });
+
+    group.bench_function("bitmask", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for mask in subsets_of_size(20, 5) {
+                total += members(mask).last().unwrap_or(0);
+            }
+            black_box(total)
+        });
+    });
+
+    group.bench_function("itertools", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for combination in (0..20).combinations(5) {
+                total += combination[4];
+            }
+            black_box(total)
+        });
+    });
+
+    group.finish();
+}
+
+fn powerset(c: &mut Criterion) {
+    let mut group = c.benchmark_group("subsets of 16");
+
+    group.bench_function("bitmask", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for mask in subsets(16) {
+                total += members(mask).sum::<usize>();
+            }
+            black_box(total)
+        });
+    });
+
+    group.bench_function("itertools", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for subset in (0..16).powerset() {
+                total += subset.iter().sum::<usize>();
+            }
+            black_box(total)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    permutations,
+    day09_routes,
+    cycles,
+    combinations,
+    powerset
+);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Day 1 part 1 over 16MiB of brackets: the original `char` loop against each of the
+//! byte kernels, and the whole stream read through in chunks.
+
+#![warn(clippy::pedantic)]
+
+use std::hint::black_box;
+
+use aoc2015::floors;
+use aoc2015::rng::XorShift;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const SIZE: usize = 16 * 1024 * 1024;
+
+/// Day 1's `get_floor` as it was before streaming.
+fn chars(s: &str) -> Result<i32, String> {
+    let mut floor = 0;
+    for char in s.chars() {
+        match char {
+            '(' => floor += 1,
+            ')' => floor -= 1,
+            _ => return Err(format!("Invalid character: '{char}'")),
+        }
+    }
+    Ok(floor)
+}
+
+fn day01_part1(c: &mut Criterion) {
+    let mut rng = XorShift::new(1);
+    let input: String = (0..SIZE)
+        .map(|_| if rng.chance(1, 2) { '(' } else { ')' })
+        .collect();
+    let bytes = input.as_bytes();
+    let want = floors::count_scalar(bytes).unwrap();
+    assert_eq!(i64::from(chars(&input).unwrap()), want);
+    assert_eq!(floors::floor(bytes).unwrap(), want);
+
+    let mut group = c.benchmark_group("day01 part 1");
+    group.throughput(Throughput::Bytes(SIZE as u64));
+    group.bench_function("chars", |b| b.iter(|| chars(black_box(&input))));
+    group.bench_function("scalar", |b| {
+        b.iter(|| floors::count_scalar(black_box(bytes)));
+    });
+    group.bench_function("swar", |b| b.iter(|| floors::count_swar(black_box(bytes))));
+    #[cfg(target_arch = "x86_64")]
+    group.bench_function("sse2", |b| b.iter(|| floors::count_sse2(black_box(bytes))));
+    group.bench_function("stream", |b| b.iter(|| floors::floor(black_box(bytes))));
+    group.finish();
+}
+
+criterion_group!(benches, day01_part1);
+criterion_main!(benches);
@@ -41,41 +41,183 @@ What is the position of the character that causes Santa to first enter the basem
 
 #![warn(clippy::pedantic)]
 
-use anyhow::{anyhow, Result};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use aoc2015::floors::{self, Trace};
 
 const PUZZLE_INPUT: &str = include_str!("../inputs/day01.txt");
 
-fn get_floor(s: &str) -> Result<i32> {
-    let mut floor = 0;
-    for char in s.chars() {
-        match char {
-            '(' => floor += 1,
-            ')' => floor -= 1,
-            _ => return Err(anyhow!("Invalid character: '{char}'")),
+/// The widest a histogram bar gets.
+const BAR: u64 = 50;
+
+fn get_floor(s: &str) -> Result<i64> {
+    Ok(floors::floor(s.as_bytes())?)
+}
+
+fn first_char_to_enter_basement(s: &str) -> Result<u64> {
+    floors::basement(s.as_bytes())?.ok_or_else(|| anyhow!("Santa never entered the basement"))
+}
+
+/// Something to ask about Santa's route, given with `--query`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Query {
+    /// `floor=N`: where he first got to floor N
+    FirstReaching(i64),
+    /// `basement`: every time he went down into the basement
+    Basement,
+    /// `extremes`: the highest and lowest floors
+    Extremes,
+    /// `histogram`: how long he spent on each floor
+    Histogram,
+    /// `above-ground`: the longest he stayed above the ground floor
+    AboveGround,
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(floor) = s.strip_prefix("floor=") {
+            let floor = floor
+                .parse()
+                .map_err(|e| anyhow!("Invalid floor {floor:?}: {e}"))?;
+            return Ok(Query::FirstReaching(floor));
+        }
+        match s {
+            "basement" => Ok(Query::Basement),
+            "extremes" => Ok(Query::Extremes),
+            "histogram" => Ok(Query::Histogram),
+            "above-ground" => Ok(Query::AboveGround),
+            _ => bail!(
+                "Unknown query {s:?}, expected floor=N, basement, extremes, histogram or above-ground"
+            ),
+        }
+    }
+}
+
+/// `[PATH] [--query QUERY]...`, the path being a file of instructions to use instead of the
+/// puzzle input.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    path: Option<String>,
+    queries: Vec<Query>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--query" => {
+                    let query = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--query needs a value"))?;
+                    options.queries.push(query.parse()?);
+                }
+                flag if flag.starts_with("--") => bail!("Unknown argument {flag:?}"),
+                path if options.path.is_none() => options.path = Some(path.to_string()),
+                extra => bail!("Unexpected argument {extra:?}, only one file can be given"),
+            }
         }
+        Ok(options)
     }
-    Ok(floor)
 }
 
-fn first_char_to_enter_basement(s: &str) -> Result<usize> {
-    let mut floor = 0;
-    for (i, char) in s.chars().enumerate() {
-        match char {
-            '(' => floor += 1,
-            ')' => floor -= 1,
-            _ => return Err(anyhow!("Invalid character: '{char}'")),
+fn answer(trace: &Trace, query: Query) -> String {
+    let mut answer = String::new();
+    match query {
+        Query::FirstReaching(floor) => match trace.first_reaching(floor) {
+            Some(position) => {
+                let _ = writeln!(answer, "Floor {floor} first reached at position {position}");
+            }
+            None => {
+                let _ = writeln!(answer, "Floor {floor} never reached");
+            }
+        },
+        Query::Basement => {
+            let entries = trace.basement_entries();
+            let positions: Vec<String> = entries.iter().map(u64::to_string).collect();
+            let _ = write!(answer, "Entered the basement {} times", entries.len());
+            if positions.is_empty() {
+                answer.push('\n');
+            } else {
+                let _ = writeln!(answer, ", at positions {}", positions.join(", "));
+            }
+        }
+        Query::Extremes => {
+            let (highest, at) = trace.highest();
+            let _ = writeln!(
+                answer,
+                "Highest floor {highest}, first reached at position {at}"
+            );
+            let (lowest, at) = trace.lowest();
+            let _ = writeln!(
+                answer,
+                "Lowest floor {lowest}, first reached at position {at}"
+            );
         }
-        if floor == -1 {
-            return Ok(i + 1);
+        Query::Histogram => {
+            let most = trace.histogram().map(|(_, time)| time).max().unwrap_or(0);
+            let _ = writeln!(answer, "{:>6} {:>8}", "Floor", "Time");
+            // Highest first, the way the building stands
+            let floors: Vec<(i64, u64)> = trace.histogram().collect();
+            for &(floor, time) in floors.iter().rev() {
+                let width = (time * BAR).checked_div(most).unwrap_or(0);
+                let bar = "#".repeat(usize::try_from(width).unwrap_or(0));
+                let _ = writeln!(answer, "{floor:>6} {time:>8} {bar}");
+            }
         }
+        Query::AboveGround => match trace.longest_above_ground() {
+            Some(run) => {
+                let _ = writeln!(
+                    answer,
+                    "Longest run above ground: positions {} to {} ({} instructions)",
+                    run.start,
+                    run.end,
+                    run.length()
+                );
+            }
+            None => {
+                let _ = writeln!(answer, "Never above ground");
+            }
+        },
     }
-    Err(anyhow!("Santa never entered the basement"))
+    answer
 }
 
 fn main() -> Result<()> {
-    let floor = get_floor(PUZZLE_INPUT)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = Options::parse(&args)?;
+    // Instructions too big to hold in memory can be streamed from a file instead
+    let open = |path: &String| File::open(path).with_context(|| format!("Could not open {path}"));
 
-    let first_in_basement = first_char_to_enter_basement(PUZZLE_INPUT)?;
+    if !options.queries.is_empty() {
+        let trace = match &options.path {
+            Some(path) => floors::trace(open(path)?)?,
+            None => floors::trace(PUZZLE_INPUT.as_bytes())?,
+        };
+        for &query in &options.queries {
+            print!("{}", answer(&trace, query));
+        }
+        return Ok(());
+    }
+
+    let (floor, first_in_basement) = match &options.path {
+        Some(path) => {
+            let floor = floors::floor(open(path)?)?;
+            let first_in_basement = floors::basement(open(path)?)?
+                .ok_or_else(|| anyhow!("Santa never entered the basement"))?;
+            (floor, first_in_basement)
+        }
+        None => (
+            get_floor(PUZZLE_INPUT)?,
+            first_char_to_enter_basement(PUZZLE_INPUT)?,
+        ),
+    };
 
     println!("Part 1: {floor}");
     println!("Part 2: {first_in_basement}");
@@ -85,6 +227,8 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use aoc2015::floors::Basement;
+    use aoc2015::rng::XorShift;
 
     #[test]
     fn test_get_floor() -> Result<()> {
@@ -101,10 +245,109 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_get_floor_invalid() {
+        let err = get_floor("(()\n").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid character '\\n' at offset 3");
+    }
+
     #[test]
     fn test_first_char_to_enter_basement() -> Result<()> {
         assert_eq!(first_char_to_enter_basement(")")?, 1);
         assert_eq!(first_char_to_enter_basement("()())")?, 5);
         Ok(())
     }
+
+    #[test]
+    fn test_generated_round_trip() -> Result<()> {
+        let mut rng = XorShift::new(46);
+        for (target, position) in [(74, 1795), (0, 1), (-20, 3), (1000, 999)] {
+            let entry = Basement::At(position);
+            for instructions in [
+                floors::shortest(target, entry)?,
+                floors::random(target, entry, 100, &mut rng)?,
+            ] {
+                assert_eq!(get_floor(&instructions)?, target);
+                assert_eq!(first_char_to_enter_basement(&instructions)?, position);
+            }
+        }
+
+        let never = floors::random(5, Basement::Never, 100, &mut rng)?;
+        assert_eq!(get_floor(&never)?, 5);
+        assert!(first_char_to_enter_basement(&never).is_err());
+        Ok(())
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        assert_eq!(Options::parse(&[])?, Options::default());
+        assert_eq!(
+            Options::parse(&args(&[
+                "big.txt",
+                "--query",
+                "floor=-3",
+                "--query",
+                "histogram"
+            ]))?,
+            Options {
+                path: Some("big.txt".to_string()),
+                queries: vec![Query::FirstReaching(-3), Query::Histogram],
+            }
+        );
+
+        let err = |list: &[&str]| Options::parse(&args(list)).unwrap_err().to_string();
+        assert_eq!(err(&["--query"]), "--query needs a value");
+        assert_eq!(err(&["--verbose"]), "Unknown argument \"--verbose\"");
+        assert_eq!(
+            err(&["a.txt", "b.txt"]),
+            "Unexpected argument \"b.txt\", only one file can be given"
+        );
+        assert!(err(&["--query", "floor=up"]).starts_with("Invalid floor \"up\""));
+        assert!(err(&["--query", "attic"]).starts_with("Unknown query \"attic\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_answers() -> Result<()> {
+        let trace = floors::trace("(()))())((".as_bytes())?;
+        assert_eq!(
+            answer(&trace, Query::FirstReaching(-2)),
+            "Floor -2 first reached at position 8\n"
+        );
+        assert_eq!(
+            answer(&trace, Query::FirstReaching(5)),
+            "Floor 5 never reached\n"
+        );
+        assert_eq!(
+            answer(&trace, Query::Basement),
+            "Entered the basement 2 times, at positions 5, 7\n"
+        );
+        assert_eq!(
+            answer(&trace, Query::Extremes),
+            "Highest floor 2, first reached at position 2\nLowest floor -2, first reached at position 8\n"
+        );
+        assert_eq!(
+            answer(&trace, Query::AboveGround),
+            "Longest run above ground: positions 1 to 3 (3 instructions)\n"
+        );
+
+        let histogram = answer(&trace, Query::Histogram);
+        let lines: Vec<&str> = histogram.lines().collect();
+        assert_eq!(lines[0], " Floor     Time");
+        assert_eq!(lines[1], format!("     2        1 {}", "#".repeat(16)));
+        assert_eq!(lines[4], format!("    -1        3 {}", "#".repeat(50)));
+        assert_eq!(lines.len(), 6);
+
+        let flat = floors::trace("".as_bytes())?;
+        assert_eq!(
+            answer(&flat, Query::Basement),
+            "Entered the basement 0 times\n"
+        );
+        assert_eq!(answer(&flat, Query::AboveGround), "Never above ground\n");
+        Ok(())
+    }
 }
@@ -34,76 +34,794 @@ How many total feet of ribbon should they order?
 
 #![warn(clippy::pedantic)]
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use aoc2015::binpack::{self, Packing};
+use aoc2015::knapsack::{self, Item, Limits, Method, Plan};
+use aoc2015::parse::{complete, integer, literal, one_of, seq};
+use json::JsonValue;
+use std::env;
+use std::fmt;
+use std::fmt::Write as _;
+use std::str::FromStr;
 
 const PUZZLE_INPUT: &str = include_str!("../inputs/day02.txt");
 
+/// How many of the hungriest presents the report summary lists.
+const LARGEST_CONSUMERS: usize = 3;
+
+/// Why a present's wrapping can't be worked out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PresentError {
+    /// A side with no size, the elves can't wrap a flat present
+    ZeroDimension(&'static str),
+    NegativeDimension(&'static str, i64),
+    /// Something bigger than a `u64` can hold
+    Overflow(&'static str),
+}
+
+impl fmt::Display for PresentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresentError::ZeroDimension(side) => write!(f, "{side} is zero"),
+            PresentError::NegativeDimension(side, value) => {
+                write!(f, "{side} is negative ({value})")
+            }
+            PresentError::Overflow(what) => write!(f, "{what} is too large to calculate"),
+        }
+    }
+}
+
+impl std::error::Error for PresentError {}
+
+/// `value`, or an overflow error naming `what` if the arithmetic behind it overflowed.
+fn checked(value: Option<u64>, what: &'static str) -> Result<u64, PresentError> {
+    value.ok_or(PresentError::Overflow(what))
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct Present {
-    length: u32,
-    width: u32,
-    height: u32,
+    length: u64,
+    width: u64,
+    height: u64,
 }
 
 impl Present {
+    /// A present with every side at least 1.
+    fn new(length: i64, width: i64, height: i64) -> Result<Present, PresentError> {
+        let side = |name, value: i64| match value {
+            0 => Err(PresentError::ZeroDimension(name)),
+            ..0 => Err(PresentError::NegativeDimension(name, value)),
+            _ => Ok(value.unsigned_abs()),
+        };
+        Ok(Present {
+            length: side("length", length)?,
+            width: side("width", width)?,
+            height: side("height", height)?,
+        })
+    }
+
+    /// Parse `LxWxH`, allowing spaces around the numbers and an upper case `X`.
     fn parse(line: &str) -> Result<Present> {
-        let parts: Vec<&str> = line.trim().split('x').collect();
-        if parts.len() != 3 {
-            return Err(anyhow!("Invalid present: {}", line));
+        let by = || one_of("xX");
+        let dimensions = seq((integer(), by(), integer(), by(), integer()));
+        let (length, _, width, _, height) =
+            complete(&dimensions, line).map_err(|e| anyhow!("Invalid present {line:?}: {e}"))?;
+
+        Present::new(length, width, height).map_err(|e| anyhow!("Invalid present {line:?}: {e}"))
+    }
+
+    fn faces(&self) -> Result<[u64; 3], PresentError> {
+        Ok([
+            checked(self.length.checked_mul(self.width), "face area")?,
+            checked(self.width.checked_mul(self.height), "face area")?,
+            checked(self.height.checked_mul(self.length), "face area")?,
+        ])
+    }
+
+    fn surface_area(&self) -> Result<u64, PresentError> {
+        let [a, b, c] = self.faces()?;
+        let sum = a.checked_add(b).and_then(|ab| ab.checked_add(c));
+        checked(sum.and_then(|sum| sum.checked_mul(2)), "surface area")
+    }
+
+    /// The area of the smallest side, added on to the paper.
+    fn slack(&self) -> Result<u64, PresentError> {
+        Ok(self.faces()?.into_iter().min().unwrap_or(0))
+    }
+
+    fn paper_required(&self) -> Result<u64, PresentError> {
+        checked(self.surface_area()?.checked_add(self.slack()?), "paper")
+    }
+
+    /// The shortest distance around any one face, for the ribbon wrapped round the present.
+    fn smallest_perimeter(&self) -> Result<u64, PresentError> {
+        // Twice the two shortest sides
+        let mut sides = [self.length, self.width, self.height];
+        sides.sort_unstable();
+        let half = sides[0].checked_add(sides[1]);
+        checked(half.and_then(|half| half.checked_mul(2)), "perimeter")
+    }
+
+    fn volume(&self) -> Result<u64, PresentError> {
+        let volume = self.length.checked_mul(self.width);
+        checked(volume.and_then(|v| v.checked_mul(self.height)), "volume")
+    }
+
+    /// The ribbon for the bow, as many feet as the present has cubic feet of volume.
+    fn bow(&self) -> Result<u64, PresentError> {
+        self.volume().map_err(|_| PresentError::Overflow("bow"))
+    }
+
+    fn ribbon_required(&self) -> Result<u64, PresentError> {
+        checked(
+            self.smallest_perimeter()?.checked_add(self.bow()?),
+            "ribbon",
+        )
+    }
+}
+
+/// The units a report is written in, the puzzle itself works in feet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Units {
+    Feet,
+    Metres,
+    Centimetres,
+}
+
+impl FromStr for Units {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Units> {
+        match s {
+            "ft" => Ok(Units::Feet),
+            "m" => Ok(Units::Metres),
+            "cm" => Ok(Units::Centimetres),
+            _ => Err(anyhow!("Unknown units {s:?}, expected ft, m or cm")),
+        }
+    }
+}
+
+impl Units {
+    fn suffix(self) -> &'static str {
+        match self {
+            Units::Feet => "ft",
+            Units::Metres => "m",
+            Units::Centimetres => "cm",
+        }
+    }
+
+    fn per_foot(self) -> f64 {
+        match self {
+            Units::Feet => 1.0,
+            Units::Metres => 0.3048,
+            Units::Centimetres => 30.48,
         }
+    }
 
-        let length = parts[0].parse()?;
-        let width = parts[1].parse()?;
-        let height = parts[2].parse()?;
+    // Reports are for reading, losing precision past 2^53 feet doesn't matter
+    #[allow(clippy::cast_precision_loss)]
+    fn length(self, feet: u64) -> f64 {
+        round(feet as f64 * self.per_foot())
+    }
 
-        Ok(Present {
-            length,
-            width,
-            height,
+    #[allow(clippy::cast_precision_loss)]
+    fn area(self, square_feet: u64) -> f64 {
+        round(square_feet as f64 * self.per_foot() * self.per_foot())
+    }
+}
+
+/// Four decimal places is plenty for wrapping paper, and keeps float noise out of reports.
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+/// Everything about wrapping one present, in the report's units.
+#[derive(Debug, PartialEq)]
+struct Breakdown {
+    /// 1-based line of the input the present came from
+    line: usize,
+    length: f64,
+    width: f64,
+    height: f64,
+    surface_area: f64,
+    slack: f64,
+    paper: f64,
+    smallest_perimeter: f64,
+    bow: f64,
+    ribbon: f64,
+}
+
+impl Breakdown {
+    /// The columns of a CSV report, areas in square units.
+    const COLUMNS: [(&'static str, bool); 10] = [
+        ("line", false),
+        ("length", false),
+        ("width", false),
+        ("height", false),
+        ("surface_area", true),
+        ("slack", true),
+        ("paper", true),
+        ("smallest_perimeter", false),
+        ("bow", false),
+        ("ribbon", false),
+    ];
+
+    fn new(line: usize, present: &Present, units: Units) -> Result<Breakdown, PresentError> {
+        Ok(Breakdown {
+            line,
+            length: units.length(present.length),
+            width: units.length(present.width),
+            height: units.length(present.height),
+            surface_area: units.area(present.surface_area()?),
+            slack: units.area(present.slack()?),
+            paper: units.area(present.paper_required()?),
+            smallest_perimeter: units.length(present.smallest_perimeter()?),
+            bow: units.length(present.bow()?),
+            ribbon: units.length(present.ribbon_required()?),
         })
     }
 
-    fn paper_required(&self) -> u32 {
-        let side1 = self.length * self.width;
-        let side2 = self.width * self.height;
-        let side3 = self.height * self.length;
+    fn values(&self) -> [f64; 9] {
+        [
+            self.length,
+            self.width,
+            self.height,
+            self.surface_area,
+            self.slack,
+            self.paper,
+            self.smallest_perimeter,
+            self.bow,
+            self.ribbon,
+        ]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Stats {
+    min: f64,
+    max: f64,
+    mean: f64,
+    total: f64,
+    /// `(line, amount)` of the presents needing the most, most first
+    largest: Vec<(usize, f64)>,
+}
+
+impl Stats {
+    fn new(breakdowns: &[Breakdown], amount: impl Fn(&Breakdown) -> f64) -> Result<Stats> {
+        if breakdowns.is_empty() {
+            bail!("No presents to summarise");
+        }
+
+        let mut amounts: Vec<(usize, f64)> =
+            breakdowns.iter().map(|b| (b.line, amount(b))).collect();
+        let total: f64 = amounts.iter().map(|(_, a)| a).sum();
+        let min = amounts
+            .iter()
+            .map(|&(_, a)| a)
+            .fold(f64::INFINITY, f64::min);
+        let max = amounts
+            .iter()
+            .map(|&(_, a)| a)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        // Most first, ties to the earliest line
+        amounts.sort_by(|(l1, a1), (l2, a2)| a2.total_cmp(a1).then(l1.cmp(l2)));
+        amounts.truncate(LARGEST_CONSUMERS);
 
-        let slack = side1.min(side2).min(side3);
+        #[allow(clippy::cast_precision_loss)] // Nobody's wrapping 2^52 presents
+        let mean = round(total / breakdowns.len() as f64);
 
-        2 * side1 + 2 * side2 + 2 * side3 + slack
+        Ok(Stats {
+            min,
+            max,
+            mean,
+            total: round(total),
+            largest: amounts,
+        })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut stats = JsonValue::new_object();
+        stats["min"] = self.min.into();
+        stats["max"] = self.max.into();
+        stats["mean"] = self.mean.into();
+        stats["total"] = self.total.into();
+        stats["largest"] = self
+            .largest
+            .iter()
+            .map(|&(line, amount)| {
+                let mut present = JsonValue::new_object();
+                present["line"] = line.into();
+                present["amount"] = amount.into();
+                present
+            })
+            .collect::<Vec<_>>()
+            .into();
+        stats
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Format> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(anyhow!("Unknown report format {s:?}, expected csv or json")),
+        }
+    }
+}
+
+/// What the sleigh planner loads the most of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Objective {
+    Count,
+    Ribbon,
+    Paper,
+}
+
+impl FromStr for Objective {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Objective> {
+        match s {
+            "count" => Ok(Objective::Count),
+            "ribbon" => Ok(Objective::Ribbon),
+            "paper" => Ok(Objective::Paper),
+            _ => Err(anyhow!(
+                "Unknown objective {s:?}, expected count, ribbon or paper"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Objective::Count => write!(f, "presents"),
+            Objective::Ribbon => write!(f, "feet of ribbon"),
+            Objective::Paper => write!(f, "square feet of paper"),
+        }
     }
+}
+
+/// A sleigh to load, in cubic feet and pounds.
+///
+/// The puzzle doesn't say what presents weigh, so every present is assumed to be equally
+/// dense: its weight is its volume times `density` pounds per cubic foot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sleigh {
+    limits: Limits,
+    objective: Objective,
+    density: u64,
+}
 
-    fn ribbon_required(&self) -> u32 {
-        let volume = self.length * self.width * self.height;
+/// Paper comes on rolls and ribbon on spools, and each present's share has to come off
+/// a single one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Material {
+    Paper,
+    Ribbon,
+}
 
-        // Perimeters of faces
-        let end_face = 2 * self.width + 2 * self.height;
-        let side_face = 2 * self.length + 2 * self.height;
-        let top_face = 2 * self.length + 2 * self.width;
+impl Material {
+    fn stock(self) -> &'static str {
+        match self {
+            Material::Paper => "roll",
+            Material::Ribbon => "spool",
+        }
+    }
 
-        let smallest = end_face.min(side_face).min(top_face);
+    fn title(self) -> &'static str {
+        match self {
+            Material::Paper => "Roll",
+            Material::Ribbon => "Spool",
+        }
+    }
 
-        smallest + volume
+    fn units(self) -> &'static str {
+        match self {
+            Material::Paper => "square feet",
+            Material::Ribbon => "feet",
+        }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stock {
+    material: Material,
+    /// How much is on each roll or spool
+    size: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    /// The usual puzzle answers
+    Answers,
+    Report(Format, Units),
+    Plan(Sleigh),
+    Cut(Stock),
+}
+
+impl Mode {
+    /// Parse one of `--report csv|json [--units ft|m|cm]`,
+    /// `--sleigh VOLUME,WEIGHT [--maximise count|ribbon|paper] [--density POUNDS]`,
+    /// `--rolls SQUARE_FEET` or `--spools FEET`, or nothing for the puzzle answers.
+    fn parse(args: &[String]) -> Result<Mode> {
+        let mut report = None;
+        let mut units = None;
+        let mut limits = None;
+        let mut objective = None;
+        let mut density = None;
+        let mut rolls = None;
+        let mut spools = None;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("{flag} needs a value"));
+            match flag.as_str() {
+                "--report" => once(&mut report, flag, value()?.parse()?)?,
+                "--units" => once(&mut units, flag, value()?.parse()?)?,
+                "--sleigh" => once(&mut limits, flag, parse_limits(value()?)?)?,
+                "--maximise" => once(&mut objective, flag, value()?.parse()?)?,
+                "--density" => once(&mut density, flag, number(flag, value()?)?)?,
+                "--rolls" => once(&mut rolls, flag, number(flag, value()?)?)?,
+                "--spools" => once(&mut spools, flag, number(flag, value()?)?)?,
+                _ => bail!("Unknown argument {flag:?}"),
+            }
+        }
+
+        let modes = [
+            report.is_some(),
+            limits.is_some(),
+            rolls.is_some(),
+            spools.is_some(),
+        ];
+        if modes.into_iter().filter(|&mode| mode).count() > 1 {
+            bail!("Only one of --report, --sleigh, --rolls or --spools can be used at a time");
+        }
+        if units.is_some() && report.is_none() {
+            bail!("--units only applies to a --report");
+        }
+        if (objective.is_some() || density.is_some()) && limits.is_none() {
+            bail!("--maximise and --density only apply to a --sleigh");
+        }
+
+        let stock = |material, size| Mode::Cut(Stock { material, size });
+        Ok(match (report, limits, rolls, spools) {
+            (Some(format), ..) => Mode::Report(format, units.unwrap_or(Units::Feet)),
+            (_, Some(limits), ..) => Mode::Plan(Sleigh {
+                limits,
+                objective: objective.unwrap_or(Objective::Count),
+                density: density.unwrap_or(1),
+            }),
+            (.., Some(size), _) => stock(Material::Paper, size),
+            (.., Some(size)) => stock(Material::Ribbon, size),
+            _ => Mode::Answers,
+        })
+    }
+}
+
+/// Set an option's `value`, which `flag` can only do once.
+fn once<T>(option: &mut Option<T>, flag: &str, value: T) -> Result<()> {
+    if option.replace(value).is_some() {
+        bail!("{flag} can only be given once");
+    }
+    Ok(())
+}
+
+fn number(flag: &str, raw: &str) -> Result<u64> {
+    raw.parse()
+        .map_err(|e| anyhow!("Invalid {flag} {raw:?}: {e}"))
+}
+
+/// `VOLUME,WEIGHT`, e.g. `50000,120000`.
+fn parse_limits(raw: &str) -> Result<Limits> {
+    let limits = seq((integer(), literal(","), integer()));
+    let (volume, _, weight) =
+        complete(&limits, raw).map_err(|e| anyhow!("Invalid sleigh {raw:?}: {e}"))?;
+    Ok(Limits { volume, weight })
+}
+
+fn breakdowns(input: &str, units: Units) -> Result<Vec<Breakdown>> {
+    input
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let present = Present::parse(line)?;
+            Breakdown::new(index + 1, &present, units)
+                .map_err(|e| anyhow!("Invalid present {line:?}: {e}"))
+        })
+        .collect()
+}
+
+/// One row per present, with each column's units in its header.
+fn csv(breakdowns: &[Breakdown], units: Units) -> String {
+    let header: Vec<String> = Breakdown::COLUMNS
+        .iter()
+        .map(|&(name, area)| match (name, area) {
+            ("line", _) => name.to_string(),
+            (_, true) => format!("{name}_{}2", units.suffix()),
+            (_, false) => format!("{name}_{}", units.suffix()),
+        })
+        .collect();
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+    for breakdown in breakdowns {
+        let values: Vec<String> = breakdown.values().iter().map(f64::to_string).collect();
+        let _ = writeln!(csv, "{},{}", breakdown.line, values.join(","));
+    }
+    csv
+}
+
+/// The min, max, mean and biggest consumers of paper and ribbon, one line each.
+fn summary(breakdowns: &[Breakdown], units: Units) -> Result<String> {
+    let mut summary = String::new();
+    for (name, stats, square) in [
+        ("paper", Stats::new(breakdowns, |b| b.paper)?, "2"),
+        ("ribbon", Stats::new(breakdowns, |b| b.ribbon)?, ""),
+    ] {
+        let unit = format!("{}{square}", units.suffix());
+        let largest: Vec<String> = stats
+            .largest
+            .iter()
+            .map(|(line, amount)| format!("line {line} ({amount}{unit})"))
+            .collect();
+        let _ = writeln!(
+            summary,
+            "{name}: total {}{unit}, min {}{unit}, max {}{unit}, mean {}{unit}, largest {}",
+            stats.total,
+            stats.min,
+            stats.max,
+            stats.mean,
+            largest.join(", ")
+        );
+    }
+    Ok(summary)
+}
+
+/// The whole report as one JSON document: units, every present and the summary.
+fn json(breakdowns: &[Breakdown], units: Units) -> Result<String> {
+    let presents: Vec<JsonValue> = breakdowns
+        .iter()
+        .map(|breakdown| {
+            let mut present = JsonValue::new_object();
+            present["line"] = breakdown.line.into();
+            for (&(name, _), value) in Breakdown::COLUMNS[1..].iter().zip(breakdown.values()) {
+                present[name] = value.into();
+            }
+            present
+        })
+        .collect();
+
+    let mut summary = JsonValue::new_object();
+    summary["count"] = breakdowns.len().into();
+    summary["paper"] = Stats::new(breakdowns, |b| b.paper)?.to_json();
+    summary["ribbon"] = Stats::new(breakdowns, |b| b.ribbon)?.to_json();
+
+    let mut report = JsonValue::new_object();
+    report["units"] = units.suffix().into();
+    report["presents"] = presents.into();
+    report["summary"] = summary;
+    Ok(report.pretty(2))
+}
+
+/// Choose which presents to load onto `sleigh`, as a plan over the input's lines in order.
+fn load(input: &str, sleigh: Sleigh) -> Result<Plan> {
+    let items = input
+        .trim()
+        .lines()
+        .map(|line| {
+            let present = Present::parse(line)?;
+            let item = || -> Result<Item, PresentError> {
+                let volume = present.volume()?;
+                Ok(Item {
+                    volume,
+                    weight: checked(volume.checked_mul(sleigh.density), "weight")?,
+                    value: match sleigh.objective {
+                        Objective::Count => 1,
+                        Objective::Ribbon => present.ribbon_required()?,
+                        Objective::Paper => present.paper_required()?,
+                    },
+                })
+            };
+            item().map_err(|e| anyhow!("Invalid present {line:?}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(knapsack::plan(&items, sleigh.limits))
+}
+
+/// `used` as a percentage of `limit`, to one decimal place.
+fn utilisation(used: u64, limit: u64) -> String {
+    if limit == 0 {
+        return "0.0%".to_string();
+    }
+    #[allow(clippy::cast_precision_loss)] // Only shown to one decimal place
+    let percent = used as f64 / limit as f64 * 100.0;
+    format!("{percent:.1}%")
+}
+
+/// What went onto the sleigh, by input line, and how full it is.
+fn describe_load(plan: &Plan, sleigh: Sleigh) -> String {
+    let lines: Vec<String> = plan.chosen.iter().map(|i| (i + 1).to_string()).collect();
+    let method = match (plan.method, plan.optimal) {
+        (Method::Dynamic, _) => "dynamic programming, optimal",
+        (Method::BranchAndBound, true) => "branch and bound, optimal",
+        (Method::BranchAndBound, false) => "branch and bound, best found before giving up",
+        (Method::Greedy, true) => "greedy, optimal",
+        (Method::Greedy, false) => "greedy, not proven optimal",
+    };
+
+    let mut description = String::new();
+    let plural = if plan.chosen.len() == 1 { "" } else { "s" };
+    let _ = write!(description, "Loaded {} present{plural}", plan.chosen.len());
+    if sleigh.objective != Objective::Count {
+        let _ = write!(description, ", {} {}", plan.value, sleigh.objective);
+    }
+    description.push('\n');
+    let _ = writeln!(description, "Lines: {}", lines.join(", "));
+    let _ = writeln!(
+        description,
+        "Volume: {} of {} cubic feet ({})",
+        plan.volume,
+        sleigh.limits.volume,
+        utilisation(plan.volume, sleigh.limits.volume)
+    );
+    let _ = writeln!(
+        description,
+        "Weight: {} of {} pounds ({})",
+        plan.weight,
+        sleigh.limits.weight,
+        utilisation(plan.weight, sleigh.limits.weight)
+    );
+    let _ = writeln!(description, "Method: {method}");
+    description
+}
+
+/// Each present's paper or ribbon, in input order, packed onto as few rolls or spools as
+/// possible.
+fn cut(input: &str, stock: Stock) -> Result<(Vec<u64>, Packing)> {
+    let sizes = input
+        .trim()
+        .lines()
+        .map(|line| {
+            let present = Present::parse(line)?;
+            let size = match stock.material {
+                Material::Paper => present.paper_required(),
+                Material::Ribbon => present.ribbon_required(),
+            };
+            size.map_err(|e| anyhow!("Invalid present {line:?}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let packing = binpack::pack(&sizes, stock.size).map_err(|e| {
+        anyhow!(
+            "The present on line {} needs {} {}, more than a whole {}",
+            e.index + 1,
+            e.size,
+            stock.material.units(),
+            stock.material.stock()
+        )
+    })?;
+    Ok((sizes, packing))
+}
+
+/// How many rolls or spools, which presents come off each, and what's wasted.
+fn describe_cut(sizes: &[u64], packing: &Packing, stock: Stock) -> Result<String> {
+    let (name, units) = (stock.material.stock(), stock.material.units());
+    let method = match packing.method {
+        binpack::Method::FirstFitDecreasing => "first fit decreasing",
+        binpack::Method::Exact => "exact search",
+    };
+
+    let mut description = String::new();
+    let _ = writeln!(
+        description,
+        "{} {name}s of {} {units}, by {method}",
+        packing.bins.len(),
+        stock.size
+    );
+    let _ = writeln!(
+        description,
+        "At least {} {name}s are needed, so this is {}",
+        packing.lower_bound,
+        if packing.optimal {
+            "optimal"
+        } else {
+            "possibly not optimal"
+        }
+    );
+    for (number, (bin, used)) in packing.bins.iter().zip(packing.used(sizes)).enumerate() {
+        let lines: Vec<String> = bin.iter().map(|i| (i + 1).to_string()).collect();
+        let _ = writeln!(
+            description,
+            "{} {}: lines {} ({used} used, {} left)",
+            stock.material.title(),
+            number + 1,
+            lines.join(", "),
+            stock.size - used
+        );
+    }
+    let waste = checked(packing.waste(sizes, stock.size), "total waste")?;
+    let total = checked(
+        u64::try_from(packing.bins.len())
+            .ok()
+            .and_then(|bins| stock.size.checked_mul(bins)),
+        "total stock",
+    )?;
+    let _ = writeln!(
+        description,
+        "Waste: {waste} {units} ({})",
+        utilisation(waste, total)
+    );
+    Ok(description)
+}
+
 fn main() -> Result<()> {
-    let mut total_paper_required = 0;
-    let mut total_ribbon_required = 0;
-    for line in PUZZLE_INPUT.trim().lines() {
-        let present = Present::parse(line)?;
-        total_paper_required += present.paper_required();
-        total_ribbon_required += present.ribbon_required();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match Mode::parse(&args)? {
+        Mode::Report(Format::Csv, units) => {
+            // The summary goes to stderr so stdout stays a clean table
+            let breakdowns = breakdowns(PUZZLE_INPUT, units)?;
+            print!("{}", csv(&breakdowns, units));
+            eprint!("{}", summary(&breakdowns, units)?);
+        }
+        Mode::Report(Format::Json, units) => {
+            let breakdowns = breakdowns(PUZZLE_INPUT, units)?;
+            println!("{}", json(&breakdowns, units)?);
+        }
+        Mode::Plan(sleigh) => {
+            let plan = load(PUZZLE_INPUT, sleigh)?;
+            print!("{}", describe_load(&plan, sleigh));
+        }
+        Mode::Cut(stock) => {
+            let (sizes, packing) = cut(PUZZLE_INPUT, stock)?;
+            print!("{}", describe_cut(&sizes, &packing, stock)?);
+        }
+        Mode::Answers => {
+            let mut total_paper_required: u64 = 0;
+            let mut total_ribbon_required: u64 = 0;
+            for line in PUZZLE_INPUT.trim().lines() {
+                let present = Present::parse(line)?;
+                total_paper_required = checked(
+                    total_paper_required.checked_add(present.paper_required()?),
+                    "total paper",
+                )?;
+                total_ribbon_required = checked(
+                    total_ribbon_required.checked_add(present.ribbon_required()?),
+                    "total ribbon",
+                )?;
+            }
+
+            println!("Part 1: {total_paper_required}");
+            println!("Part 2: {total_ribbon_required}");
+        }
     }
 
-    println!("Part 1: {total_paper_required}");
-    println!("Part 2: {total_ribbon_required}");
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use aoc2015::rng::XorShift;
+
+    const TEST_INPUT: &str = "2x3x4\n1x1x10";
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
 
     #[test]
     fn test_present_parse() -> Result<()> {
@@ -115,6 +833,151 @@ mod test {
         };
 
         assert_eq!(present, want);
+
+        let err = Present::parse("2x3y4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid present "2x3y4": expected `x` or `X` at column 4, found `y4`"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_present_parse_variants() -> Result<()> {
+        let want = Present {
+            length: 2,
+            width: 3,
+            height: 4,
+        };
+        assert_eq!(Present::parse("2 X 3 x 4")?, want);
+        assert_eq!(Present::parse("  2X3X4 ")?, want);
+        assert_eq!(Present::parse("2\tx 3 x\t4")?, want);
+        Ok(())
+    }
+
+    #[test]
+    fn test_present_dimension_errors() {
+        assert_eq!(
+            Present::new(2, 0, 4),
+            Err(PresentError::ZeroDimension("width"))
+        );
+        assert_eq!(
+            Present::new(-2, 3, 4),
+            Err(PresentError::NegativeDimension("length", -2))
+        );
+
+        let err = Present::parse("2x3x0").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid present "2x3x0": height is zero"#
+        );
+        let err = Present::parse("2x-3x4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid present "2x-3x4": width is negative (-3)"#
+        );
+    }
+
+    #[test]
+    fn test_present_overflow() -> Result<()> {
+        // A volume that fits, with too much surface to wrap
+        let present = Present::new(1 << 32, 1 << 31, 1)?;
+        assert_eq!(
+            present.paper_required(),
+            Err(PresentError::Overflow("surface area"))
+        );
+        assert_eq!(present.ribbon_required(), Ok(2 + 2 * (1 << 31) + (1 << 63)));
+
+        let present = Present::new(1 << 22, 1 << 22, 1 << 22)?;
+        assert!(present.paper_required().is_ok());
+        assert_eq!(present.bow(), Err(PresentError::Overflow("bow")));
+        assert_eq!(
+            present.ribbon_required().unwrap_err().to_string(),
+            "bow is too large to calculate"
+        );
+        Ok(())
+    }
+
+    /// Paper and ribbon worked out in `u128`, which can't overflow for sides below 2^42.
+    fn reference(present: &Present) -> (u128, u128) {
+        let (l, w, h) = (
+            u128::from(present.length),
+            u128::from(present.width),
+            u128::from(present.height),
+        );
+        let faces = [l * w, w * h, h * l];
+        let paper = 2 * faces.iter().sum::<u128>() + faces.iter().min().unwrap();
+        let mut sides = [l, w, h];
+        sides.sort_unstable();
+        let ribbon = 2 * (sides[0] + sides[1]) + l * w * h;
+        (paper, ribbon)
+    }
+
+    /// A side from one of a few scales, so some presents overflow and most don't.
+    fn side(rng: &mut XorShift) -> u64 {
+        let bits = *rng.pick(&[4, 16, 21, 32, 42]).unwrap();
+        rng.between(1..=(1 << bits) - 1)
+    }
+
+    #[test]
+    fn test_matches_big_integer_reference() {
+        let mut rng = XorShift::new(2015);
+        for _ in 0..10_000 {
+            let present = Present {
+                length: side(&mut rng),
+                width: side(&mut rng),
+                height: side(&mut rng),
+            };
+            let (paper, ribbon) = reference(&present);
+
+            match present.paper_required() {
+                Ok(got) => assert_eq!(u128::from(got), paper, "{present:?}"),
+                Err(e) => {
+                    assert!(paper > u128::from(u64::MAX), "{present:?}: {e}");
+                    assert!(matches!(e, PresentError::Overflow(_)));
+                }
+            }
+            match present.ribbon_required() {
+                Ok(got) => assert_eq!(u128::from(got), ribbon, "{present:?}"),
+                Err(e) => {
+                    assert!(ribbon > u128::from(u64::MAX), "{present:?}: {e}");
+                    assert!(matches!(e, PresentError::Overflow(_)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_totals_match_big_integer_reference() -> Result<()> {
+        // Realistic presents, totalled through the report
+        let mut rng = XorShift::new(25);
+        let mut input = String::new();
+        let (mut paper, mut ribbon) = (0u128, 0u128);
+        for _ in 0..1000 {
+            let present = Present {
+                length: rng.between(1..=30),
+                width: rng.between(1..=30),
+                height: rng.between(1..=30),
+            };
+            let _ = writeln!(
+                input,
+                "{}x{}x{}",
+                present.length, present.width, present.height
+            );
+            let (present_paper, present_ribbon) = reference(&present);
+            paper += present_paper;
+            ribbon += present_ribbon;
+        }
+
+        let breakdowns = breakdowns(&input, Units::Feet)?;
+        let stats = Stats::new(&breakdowns, |b| b.paper)?;
+        #[allow(clippy::cast_precision_loss)]
+        let want = paper as f64;
+        assert_eq!(stats.total.to_string(), want.to_string());
+        let stats = Stats::new(&breakdowns, |b| b.ribbon)?;
+        #[allow(clippy::cast_precision_loss)]
+        let want = ribbon as f64;
+        assert_eq!(stats.total.to_string(), want.to_string());
         Ok(())
     }
 
@@ -125,14 +988,14 @@ mod test {
             width: 3,
             height: 4,
         };
-        assert_eq!(present.paper_required(), 58);
+        assert_eq!(present.paper_required(), Ok(58));
 
         let present = Present {
             length: 1,
             width: 1,
             height: 10,
         };
-        assert_eq!(present.paper_required(), 43);
+        assert_eq!(present.paper_required(), Ok(43));
     }
 
     #[test]
@@ -142,13 +1005,306 @@ mod test {
             width: 3,
             height: 4,
         };
-        assert_eq!(present.ribbon_required(), 34);
+        assert_eq!(present.ribbon_required(), Ok(34));
 
         let present = Present {
             length: 1,
             width: 1,
             height: 10,
         };
-        assert_eq!(present.ribbon_required(), 14);
+        assert_eq!(present.ribbon_required(), Ok(14));
+    }
+
+    #[test]
+    fn test_breakdown() -> Result<()> {
+        let breakdowns = breakdowns(TEST_INPUT, Units::Feet)?;
+        assert_eq!(
+            breakdowns[0],
+            Breakdown {
+                line: 1,
+                length: 2.0,
+                width: 3.0,
+                height: 4.0,
+                surface_area: 52.0,
+                slack: 6.0,
+                paper: 58.0,
+                smallest_perimeter: 10.0,
+                bow: 24.0,
+                ribbon: 34.0,
+            }
+        );
+        assert_eq!(
+            breakdowns[1],
+            Breakdown {
+                line: 2,
+                length: 1.0,
+                width: 1.0,
+                height: 10.0,
+                surface_area: 42.0,
+                slack: 1.0,
+                paper: 43.0,
+                smallest_perimeter: 4.0,
+                bow: 10.0,
+                ribbon: 14.0,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_units() -> Result<()> {
+        // Compared as the report prints them, 58 square feet is 5.38837632 square metres
+        let metres = &breakdowns("2x3x4", Units::Metres)?[0];
+        assert_eq!(metres.length.to_string(), "0.6096");
+        assert_eq!(metres.paper.to_string(), "5.3884");
+        assert_eq!(metres.ribbon.to_string(), "10.3632");
+
+        let centimetres = &breakdowns("2x3x4", Units::Centimetres)?[0];
+        assert_eq!(centimetres.paper.to_string(), "53883.7632");
+        assert_eq!(centimetres.ribbon.to_string(), "1036.32");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let breakdowns = breakdowns(TEST_INPUT, Units::Feet)?;
+        let paper = Stats::new(&breakdowns, |b| b.paper)?;
+        assert_eq!(
+            paper,
+            Stats {
+                min: 43.0,
+                max: 58.0,
+                mean: 50.5,
+                total: 101.0,
+                largest: vec![(1, 58.0), (2, 43.0)],
+            }
+        );
+
+        assert!(Stats::new(&[], |b| b.paper).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv() -> Result<()> {
+        let breakdowns = breakdowns(TEST_INPUT, Units::Feet)?;
+        assert_eq!(
+            csv(&breakdowns, Units::Feet),
+            "line,length_ft,width_ft,height_ft,surface_area_ft2,slack_ft2,paper_ft2,smallest_perimeter_ft,bow_ft,ribbon_ft\n\
+             1,2,3,4,52,6,58,10,24,34\n\
+             2,1,1,10,42,1,43,4,10,14\n"
+        );
+        assert_eq!(
+            summary(&breakdowns, Units::Feet)?,
+            "paper: total 101ft2, min 43ft2, max 58ft2, mean 50.5ft2, largest line 1 (58ft2), line 2 (43ft2)\n\
+             ribbon: total 48ft, min 14ft, max 34ft, mean 24ft, largest line 1 (34ft), line 2 (14ft)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<()> {
+        let breakdowns = breakdowns(TEST_INPUT, Units::Feet)?;
+        let report = json::parse(&json(&breakdowns, Units::Feet)?)?;
+
+        assert_eq!(report["units"], "ft");
+        assert_eq!(report["presents"].len(), 2);
+        assert_eq!(report["presents"][0]["paper"], 58);
+        assert_eq!(report["presents"][0]["ribbon"], 34);
+        assert_eq!(report["presents"][1]["smallest_perimeter"], 4);
+        assert_eq!(report["summary"]["count"], 2);
+        assert_eq!(report["summary"]["ribbon"]["mean"], 24);
+        assert_eq!(report["summary"]["paper"]["largest"][0]["line"], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_modes() -> Result<()> {
+        assert_eq!(Mode::parse(&args(&[]))?, Mode::Answers);
+        assert_eq!(
+            Mode::parse(&args(&["--units", "cm", "--report", "json"]))?,
+            Mode::Report(Format::Json, Units::Centimetres)
+        );
+        assert_eq!(
+            Mode::parse(&args(&["--sleigh", "100, 200", "--maximise", "ribbon"]))?,
+            Mode::Plan(Sleigh {
+                limits: Limits {
+                    volume: 100,
+                    weight: 200
+                },
+                objective: Objective::Ribbon,
+                density: 1,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mode_errors() {
+        let err = |list: &[&str]| Mode::parse(&args(list)).unwrap_err().to_string();
+
+        assert_eq!(err(&["--units", "m"]), "--units only applies to a --report");
+        assert_eq!(
+            err(&["--report", "xml"]),
+            r#"Unknown report format "xml", expected csv or json"#
+        );
+        assert_eq!(err(&["--report"]), "--report needs a value");
+        assert_eq!(
+            err(&["--units", "in", "--report", "csv"]),
+            r#"Unknown units "in", expected ft, m or cm"#
+        );
+        assert_eq!(
+            err(&["--report", "csv", "--sleigh", "1,1"]),
+            "Only one of --report, --sleigh, --rolls or --spools can be used at a time"
+        );
+        assert_eq!(
+            err(&["--rolls", "100", "--spools", "50"]),
+            "Only one of --report, --sleigh, --rolls or --spools can be used at a time"
+        );
+        assert_eq!(
+            err(&["--rolls", "lots"]),
+            r#"Invalid --rolls "lots": invalid digit found in string"#
+        );
+        assert_eq!(
+            err(&["--density", "2"]),
+            "--maximise and --density only apply to a --sleigh"
+        );
+        assert_eq!(
+            err(&["--sleigh", "100x200"]),
+            r#"Invalid sleigh "100x200": expected `,` at column 4, found `x200`"#
+        );
+        assert_eq!(
+            err(&["--sleigh", "1,1", "--maximise", "joy"]),
+            r#"Unknown objective "joy", expected count, ribbon or paper"#
+        );
+        assert_eq!(
+            err(&["--report", "csv", "--units", "m", "--units", "cm"]),
+            "--units can only be given once"
+        );
+        assert_eq!(
+            err(&["--rolls", "10", "--rolls", "10"]),
+            "--rolls can only be given once"
+        );
+    }
+
+    #[test]
+    fn test_cut_rolls() -> Result<()> {
+        assert_eq!(
+            Mode::parse(&args(&["--rolls", "100"]))?,
+            Mode::Cut(Stock {
+                material: Material::Paper,
+                size: 100
+            })
+        );
+
+        // 58 and 43 square feet don't fit on one roll of 100
+        let stock = Stock {
+            material: Material::Paper,
+            size: 100,
+        };
+        let (sizes, packing) = cut(TEST_INPUT, stock)?;
+        assert_eq!(sizes, vec![58, 43]);
+        assert_eq!(
+            describe_cut(&sizes, &packing, stock)?,
+            "2 rolls of 100 square feet, by first fit decreasing\n\
+             At least 2 rolls are needed, so this is optimal\n\
+             Roll 1: lines 1 (58 used, 42 left)\n\
+             Roll 2: lines 2 (43 used, 57 left)\n\
+             Waste: 99 square feet (49.5%)\n"
+        );
+
+        // 34 and 14 feet of ribbon do fit on a spool of 50
+        let stock = Stock {
+            material: Material::Ribbon,
+            size: 50,
+        };
+        let (sizes, packing) = cut(TEST_INPUT, stock)?;
+        assert_eq!(packing.bins, vec![vec![0, 1]]);
+        assert_eq!(packing.waste(&sizes, stock.size), Some(2));
+
+        // Whole spools of the biggest size add up to more than a u64, and so can the waste
+        let stock = Stock {
+            material: Material::Ribbon,
+            size: u64::MAX,
+        };
+        let full = [u64::MAX; 2];
+        let err = describe_cut(&full, &binpack::pack(&full, u64::MAX)?, stock).unwrap_err();
+        assert_eq!(err.to_string(), "total stock is too large to calculate");
+        let halves = [u64::MAX / 2 + 1; 3];
+        let err = describe_cut(&halves, &binpack::pack(&halves, u64::MAX)?, stock).unwrap_err();
+        assert_eq!(err.to_string(), "total waste is too large to calculate");
+
+        let err = cut(TEST_INPUT, Stock { size: 20, ..stock }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The present on line 1 needs 34 feet, more than a whole spool"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_sleigh() -> Result<()> {
+        // Both presents don't fit, 24 + 10 cubic feet, so take the one with more ribbon
+        let mut sleigh = Sleigh {
+            limits: Limits {
+                volume: 30,
+                weight: 60,
+            },
+            objective: Objective::Ribbon,
+            density: 1,
+        };
+        let plan = load(TEST_INPUT, sleigh)?;
+        assert_eq!(plan.chosen, vec![0]);
+        assert_eq!(plan.value, 34);
+        assert_eq!(
+            describe_load(&plan, sleigh),
+            "Loaded 1 present, 34 feet of ribbon\n\
+             Lines: 1\n\
+             Volume: 24 of 30 cubic feet (80.0%)\n\
+             Weight: 24 of 60 pounds (40.0%)\n\
+             Method: dynamic programming, optimal\n"
+        );
+
+        // Three times as dense, the 2x3x4 present is too heavy at 72 pounds
+        sleigh.density = 3;
+        let plan = load(TEST_INPUT, sleigh)?;
+        assert_eq!(plan.chosen, vec![1]);
+        assert_eq!(plan.weight, 30);
+
+        // Room for both
+        sleigh.limits.weight = 200;
+        sleigh.limits.volume = 40;
+        sleigh.objective = Objective::Count;
+        let plan = load(TEST_INPUT, sleigh)?;
+        assert_eq!(plan.chosen, vec![0, 1]);
+        assert!(describe_load(&plan, sleigh).starts_with("Loaded 2 presents\n"));
+
+        let quick = Plan {
+            method: Method::Greedy,
+            optimal: false,
+            ..plan
+        };
+        assert!(describe_load(&quick, sleigh).ends_with("Method: greedy, not proven optimal\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_more_paper_than_a_u64() -> Result<()> {
+        // Each needs 2^63 + 3 square feet of paper, so the two together overflow a u64
+        let length = 1u64 << 61;
+        let input = format!("1x1x{length}\n1x1x{length}");
+        let sleigh = Sleigh {
+            limits: Limits {
+                volume: 2 * length,
+                weight: 2 * length,
+            },
+            objective: Objective::Paper,
+            density: 1,
+        };
+        let plan = load(&input, sleigh)?;
+        assert_eq!(plan.chosen, vec![0, 1]);
+        assert_eq!(plan.value, 2 * (u128::from(length) * 4 + 3));
+        assert!(describe_load(&plan, sleigh)
+            .starts_with("Loaded 2 presents, 18446744073709551622 square feet of paper\n"));
+        Ok(())
     }
 }
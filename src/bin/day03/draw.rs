@@ -0,0 +1,391 @@
+//! Pictures of the deliveries: heatmaps of the presents and drawings of the routes.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+use aoc2015::delivery::Deliveries;
+use aoc2015::geometry::{Point, Rect};
+use aoc2015::grid::Grid;
+use aoc2015::image::{self, Rgb};
+
+/// How wide a house is when rendering a route to SVG, and the space around the edge.
+const CELL: i64 = 10;
+const MARGIN: i64 = 10;
+
+/// How many pixels wide a house is when rendering a route to PPM. It's odd so each house
+/// has a middle pixel for the route to go through.
+const PIXELS: usize = 3;
+
+/// The most pixels a heatmap or a PPM of the routes can have, which is about 50MB of colour.
+/// Starts far apart spread the houses out over more than that.
+const MOST_PIXELS: u64 = 16_000_000;
+
+/// One colour per agent, starting with Santa in red and Robo-Santa in blue, then going
+/// round again if there are more agents than colours.
+pub const PALETTE: [Rgb; 8] = [
+    [214, 39, 40],
+    [31, 119, 180],
+    [44, 160, 44],
+    [255, 127, 14],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [188, 189, 34],
+];
+
+/// Where `--heatmap` goes, picked by the file extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Heatmap {
+    Pgm(PathBuf),
+    Ppm(PathBuf),
+    /// Coloured blocks straight to the terminal
+    Ansi,
+}
+
+impl FromStr for Heatmap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "ansi" {
+            return Ok(Heatmap::Ansi);
+        }
+        let path = PathBuf::from(s);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("pgm") => Ok(Heatmap::Pgm(path)),
+            Some("ppm") => Ok(Heatmap::Ppm(path)),
+            _ => bail!("Unknown heatmap {s:?}, expected a .pgm or .ppm file, or ansi"),
+        }
+    }
+}
+
+/// Where `render` draws the routes, picked by the file extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Picture {
+    Svg(PathBuf),
+    Ppm(PathBuf),
+}
+
+impl FromStr for Picture {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = PathBuf::from(s);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => Ok(Picture::Svg(path)),
+            Some("ppm") => Ok(Picture::Ppm(path)),
+            _ => bail!("Unknown picture {s:?}, expected a .svg or .ppm file"),
+        }
+    }
+}
+
+/// The houses anyone delivered to. There's always at least one agent, who delivers to
+/// their starting house before anything else.
+fn bounds(deliveries: &Deliveries) -> Rect<i32> {
+    deliveries.bounds().expect("Someone delivered a present")
+}
+
+fn size(length: i32) -> usize {
+    usize::try_from(length).expect("Bounds aren't empty")
+}
+
+/// The width and height in pixels of a picture of `bounds`, each house `scale` pixels
+/// square, as long as that's no more than [`MOST_PIXELS`].
+fn canvas(bounds: Rect<i32>, scale: usize) -> Result<(usize, usize)> {
+    let (width, height) = bounds.span();
+    let pixels = u64::try_from(scale * scale)
+        .ok()
+        .and_then(|square| width.checked_mul(height)?.checked_mul(square));
+    match pixels {
+        Some(pixels) if pixels <= MOST_PIXELS => Ok((
+            usize::try_from(width)? * scale,
+            usize::try_from(height)? * scale,
+        )),
+        _ => bail!("The houses cover {width} x {height}, too much to draw in {MOST_PIXELS} pixels"),
+    }
+}
+
+/// How bright a house with `count` presents is when the most is `most`. It's a log scale,
+/// so houses with a handful still show up next to the pile at the start.
+pub fn level(count: usize, most: usize) -> u8 {
+    if count == 0 {
+        return 0;
+    }
+    // Counts are nowhere near big enough to lose precision, and the result is in 0..=255
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let level = ((count as f64).ln_1p() / (most as f64).ln_1p() * 255.0).round() as u8;
+    level.max(1)
+}
+
+/// The presents at every house in the bounds, scaled to 0 for none up to 255 for the most.
+///
+/// # Errors
+///
+/// If there are too many houses in the bounds to draw.
+pub fn heat(deliveries: &Deliveries) -> Result<Grid<u8>> {
+    let bounds = bounds(deliveries);
+    let (width, height) = canvas(bounds, 1)?;
+    let presents = deliveries.presents();
+    let most = presents.values().copied().max().unwrap_or(0);
+    let mut grid = Grid::new(width, height, 0);
+    for (house, count) in presents {
+        let offset = house - bounds.min;
+        grid[Point::new(size(offset.x), size(offset.y))] = level(count, most);
+    }
+    Ok(grid)
+}
+
+/// The heat as 24-bit coloured blocks, two characters a house so it comes out square.
+pub fn ansi(heat: &Grid<u8>) -> String {
+    let mut out = String::new();
+    for row in heat.rows() {
+        for &level in row {
+            if level == 0 {
+                out.push_str("  ");
+            } else {
+                let [r, g, b] = image::heat(level);
+                let _ = write!(out, "\x1b[38;2;{r};{g};{b}m██");
+            }
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+pub fn colour(heat: &Grid<u8>) -> Grid<Rgb> {
+    let cells = heat.iter().map(|&level| image::heat(level)).collect();
+    Grid::from_vec(heat.width(), heat.height(), cells).expect("Same size as the heat")
+}
+
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// The routes drawn over the houses they visit, a coloured line per agent from a circle
+/// where they start to a square where they finish. With `labels`, every that many of an
+/// agent's moves is numbered along their route.
+pub fn svg(routes: &[Vec<Point<i32>>], deliveries: &Deliveries, labels: Option<usize>) -> String {
+    let bounds = bounds(deliveries);
+    let centre = |house: Point<i32>| {
+        (
+            i64::from(house.x - bounds.min.x) * CELL + MARGIN + CELL / 2,
+            i64::from(house.y - bounds.min.y) * CELL + MARGIN + CELL / 2,
+        )
+    };
+    // Spans are at most 2^32, so these have room to spare
+    let (width, height) = bounds.span();
+    let width = i64::try_from(width).expect("Span fits") * CELL + 2 * MARGIN;
+    let height = i64::try_from(height).expect("Span fits") * CELL + 2 * MARGIN;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    // Sorted so the same routes always make the same file
+    let mut houses: Vec<Point<i32>> = deliveries.presents().into_keys().collect();
+    houses.sort_unstable_by_key(|house| (house.y, house.x));
+    let _ = writeln!(svg, r##"<g fill="#e8e8e8">"##);
+    for house in houses {
+        let (x, y) = centre(house);
+        let (x, y) = (x - CELL / 2, y - CELL / 2);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}"/>"#
+        );
+    }
+    let _ = writeln!(svg, "</g>");
+
+    for (agent, route) in routes.iter().enumerate() {
+        let colour = hex(PALETTE[agent % PALETTE.len()]);
+        let points: Vec<String> = route
+            .iter()
+            .map(|&house| {
+                let (x, y) = centre(house);
+                format!("{x},{y}")
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{colour}" stroke-width="2" stroke-opacity="0.7" stroke-linejoin="round" points="{}"><title>Agent {agent}</title></polyline>"#,
+            points.join(" ")
+        );
+
+        let (x, y) = centre(route[0]);
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{x}" cy="{y}" r="{}" fill="{colour}" stroke="black"><title>Agent {agent} starts</title></circle>"#,
+            CELL * 2 / 5
+        );
+        let (x, y) = centre(route[route.len() - 1]);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{colour}" stroke="black"><title>Agent {agent} finishes</title></rect>"#,
+            x - CELL * 2 / 5,
+            y - CELL * 2 / 5,
+            CELL * 4 / 5,
+            CELL * 4 / 5
+        );
+
+        for (step, &house) in route.iter().enumerate().skip(1) {
+            if labels.is_some_and(|every| step % every == 0) {
+                let (x, y) = centre(house);
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{x}" y="{y}" font-size="{}" fill="{colour}">{step}</text>"#,
+                    CELL / 2
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The routes as pixels, each house a `PIXELS` square: light grey if anyone visited it,
+/// with a line through the middles in each agent's colour. Starts are filled in and
+/// finishes outlined in white.
+///
+/// # Errors
+///
+/// If there are too many houses in the bounds to draw.
+pub fn raster(routes: &[Vec<Point<i32>>], deliveries: &Deliveries) -> Result<Grid<Rgb>> {
+    let bounds = bounds(deliveries);
+    let (width, height) = canvas(bounds, PIXELS)?;
+    let mut grid = Grid::new(width, height, [255; 3]);
+    // The top left pixel of a house
+    let corner = |house: Point<i32>| {
+        let offset = house - bounds.min;
+        Point::new(size(offset.x) * PIXELS, size(offset.y) * PIXELS)
+    };
+    let square = |grid: &mut Grid<Rgb>, house, colour| {
+        let corner = corner(house);
+        let far = Point::new(corner.x + PIXELS - 1, corner.y + PIXELS - 1);
+        for pixel in grid.view_mut(Rect::new(corner, far)).iter_mut() {
+            *pixel = colour;
+        }
+    };
+
+    for house in deliveries.presents().into_keys() {
+        square(&mut grid, house, [232; 3]);
+    }
+
+    let middle = PIXELS / 2;
+    for (agent, route) in routes.iter().enumerate() {
+        let colour = PALETTE[agent % PALETTE.len()];
+        square(&mut grid, route[0], colour);
+        let end = route[route.len() - 1];
+        square(&mut grid, end, [255; 3]);
+        for pair in route.windows(2) {
+            let (from, to) = (corner(pair[0]), corner(pair[1]));
+            // Moves are one house at a time, so this is a straight line along a row or column
+            let line = Rect::new(
+                Point::new(from.x + middle, from.y + middle),
+                Point::new(to.x + middle, to.y + middle),
+            );
+            for pixel in grid.view_mut(line).iter_mut() {
+                *pixel = colour;
+            }
+        }
+        let end = corner(end);
+        grid[Point::new(end.x + middle, end.y + middle)] = colour;
+    }
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aoc2015::delivery::{deliver, routes, Schedule, ORIGIN};
+
+    #[test]
+    fn test_heat() -> Result<()> {
+        let deliveries = deliver("^v^v>", &[ORIGIN], &Schedule::RoundRobin)?;
+        let heat = heat(&deliveries)?;
+        assert_eq!((heat.width(), heat.height()), (2, 2));
+        // The top row is y = -1
+        assert_eq!(heat.row(0), [202, 0]);
+        assert_eq!(heat.row(1), [255, 128]);
+
+        assert_eq!(level(0, 10), 0);
+        assert_eq!(level(10, 10), 255);
+        assert_eq!(level(1, 1_000_000), 13);
+
+        let ansi = ansi(&heat);
+        assert_eq!(ansi.lines().count(), 2);
+        assert!(ansi.lines().next().unwrap().ends_with("██  \x1b[0m"));
+        assert_eq!(
+            image::ppm(&colour(&heat)).len(),
+            "P6\n2 2\n255\n".len() + 12
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_svg() -> Result<()> {
+        let starts = [ORIGIN, ORIGIN];
+        let routes = routes("^>>", &starts, &Schedule::RoundRobin)?;
+        let deliveries = deliver("^>>", &starts, &Schedule::RoundRobin)?;
+        let drawn = svg(&routes, &deliveries, Some(1));
+
+        // Houses 0,-1 to 1,0 make a 2 x 2 picture, plus the margins
+        assert!(drawn.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40""#));
+        assert!(drawn.ends_with("</svg>\n"));
+        assert_eq!(drawn.matches(r#"width="10" height="10"/>"#).count(), 4);
+        assert!(drawn.contains(r##"<polyline fill="none" stroke="#d62728" stroke-width="2" stroke-opacity="0.7" stroke-linejoin="round" points="15,25 15,15 25,15">"##));
+        assert!(drawn.contains(r##"stroke="#1f77b4" stroke-width="2" stroke-opacity="0.7" stroke-linejoin="round" points="15,25 25,25">"##));
+        assert_eq!(drawn.matches("<circle").count(), 2);
+        assert_eq!(drawn.matches("finishes</title>").count(), 2);
+        assert_eq!(drawn.matches("<text").count(), 3);
+        assert!(!svg(&routes, &deliveries, None).contains("<text"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_raster() -> Result<()> {
+        let short = routes(">", &[ORIGIN], &Schedule::RoundRobin)?;
+        let deliveries = deliver(">", &[ORIGIN], &Schedule::RoundRobin)?;
+        let grid = raster(&short, &deliveries)?;
+        let santa = PALETTE[0];
+        let (white, grey) = ([255; 3], [232; 3]);
+
+        assert_eq!((grid.width(), grid.height()), (6, 3));
+        // The start filled in, then a line through the middle to a white outlined finish
+        assert_eq!(grid.row(0), [santa, santa, santa, white, white, white]);
+        assert_eq!(grid.row(1), [santa, santa, santa, santa, santa, white]);
+        assert!(!grid.iter().any(|&pixel| pixel == grey));
+
+        let longer = routes(">>", &[ORIGIN], &Schedule::RoundRobin)?;
+        let deliveries = deliver(">>", &[ORIGIN], &Schedule::RoundRobin)?;
+        let grid = raster(&longer, &deliveries)?;
+        assert_eq!(grid.row(0)[3..6], [grey, grey, grey]);
+        assert_eq!(grid.row(1)[3..6], [santa, santa, santa]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_too_big_to_draw() -> Result<()> {
+        // 2000 x 1001 houses is fine as a heatmap, but not at 3 x 3 pixels each
+        let starts = [Point::new(0, 0), Point::new(1999, 1000)];
+        let deliveries = deliver(">", &starts, &Schedule::RoundRobin)?;
+        let routes = routes(">", &starts, &Schedule::RoundRobin)?;
+        assert!(heat(&deliveries).is_ok());
+        assert_eq!(
+            raster(&routes, &deliveries).unwrap_err().to_string(),
+            "The houses cover 2000 x 1001, too much to draw in 16000000 pixels"
+        );
+
+        let starts = [Point::new(0, 0), Point::new(100_000, 100_000)];
+        let deliveries = deliver(">", &starts, &Schedule::RoundRobin)?;
+        assert!(heat(&deliveries).is_err());
+        Ok(())
+    }
+}
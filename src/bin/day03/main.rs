@@ -0,0 +1,616 @@
+/*
+--- Day 3: Perfectly Spherical Houses in a Vacuum ---
+
+Santa is delivering presents to an infinite two-dimensional grid of houses.
+
+He begins by delivering a present to the house at his starting location, and then an elf at the North Pole calls
+him via radio and tells him where to move next. Moves are always exactly one house to the north (^), south (v), east (>),
+or west (<). After each move, he delivers another present to the house at his new location.
+
+However, the elf back at the north pole has had a little too much eggnog, and so his directions are a little off,
+and Santa ends up visiting some houses more than once.
+
+How many houses receive at least one present?
+
+For example:
+
+> delivers presents to 2 houses: one at the starting location, and one to the east.
+^>v< delivers presents to 4 houses in a square, including twice to the house at his starting/ending location.
+^v^v^v^v^v delivers a bunch of presents to some very lucky children at only 2 houses.
+
+--- Part Two ---
+
+The next year, to speed up the process, Santa creates a robot version of himself, Robo-Santa, to deliver presents with him.
+
+Santa and Robo-Santa start at the same location (delivering two presents to the same starting house), then take
+turns moving based on instructions from the elf, who is eggnoggedly reading from the same script as the previous year.
+
+This year, how many houses receive at least one present?
+
+For example:
+
+^v delivers presents to 3 houses, because Santa goes north, and then Robo-Santa goes south.
+^>v< now delivers presents to 3 houses, and Santa and Robo-Santa end up back where they started.
+^v^v^v^v^v now delivers presents to 11 houses, with Santa going one direction and Robo-Santa going the other.
+*/
+
+#![warn(clippy::pedantic)]
+
+mod draw;
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use aoc2015::delivery::{self, Deliveries, Schedule, Timeline, Visit, ORIGIN};
+use aoc2015::geometry::Point;
+use aoc2015::image;
+
+use crate::draw::{Heatmap, Picture};
+
+const PUZZLE_INPUT: &str = include_str!("../../inputs/day03.txt");
+
+/// How many of the most visited houses `--stats` lists.
+const TOP: usize = 5;
+
+/// How far from 0,0 a `--start` can be along either axis. The input is a few thousand
+/// moves, so every house stays well inside an `i32`, and so does the distance between any
+/// two of them.
+const FURTHEST_START: i32 = 1_000_000;
+
+fn count_houses_with_a_present(raw: &str) -> Result<usize> {
+    Ok(delivery::deliver(raw, &[ORIGIN], &Schedule::RoundRobin)?.distinct())
+}
+
+fn count_houses_with_robo_santa(raw: &str) -> Result<usize> {
+    Ok(delivery::deliver(raw, &[ORIGIN, ORIGIN], &Schedule::RoundRobin)?.distinct())
+}
+
+/// Something to ask about when houses were visited, given with `--query`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Query {
+    /// `first=X,Y`: when the house was first visited, and by who
+    FirstVisit(Point<i32>),
+    /// `history=X,Y`: every visit to the house
+    History(Point<i32>),
+    /// `once`: the houses that only got one present
+    VisitedOnce,
+    /// `reach=N`: how many moves it took for N distinct houses to get a present
+    Reach(usize),
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(house) = s.strip_prefix("first=") {
+            return Ok(Query::FirstVisit(house.parse()?));
+        }
+        if let Some(house) = s.strip_prefix("history=") {
+            return Ok(Query::History(house.parse()?));
+        }
+        if let Some(n) = s.strip_prefix("reach=") {
+            let n = n
+                .parse()
+                .map_err(|e| anyhow!("Invalid number of houses {n:?}: {e}"))?;
+            return Ok(Query::Reach(n));
+        }
+        match s {
+            "once" => Ok(Query::VisitedOnce),
+            _ => bail!("Unknown query {s:?}, expected first=X,Y, history=X,Y, once or reach=N"),
+        }
+    }
+}
+
+/// `--agents K`, `--start X,Y` once per agent, `--schedule SCHEDULE`, `--stats` and
+/// `--heatmap PATH|ansi`, all optional, or nothing at all for the puzzle answers. Or
+/// `--query QUERY` as many times as needed to ask about the timeline of visits, or
+/// `--render --out PATH [--labels EVERY]` to draw the routes, which is what
+/// `aoc2015 render 3` passes on.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    starts: Vec<Point<i32>>,
+    schedule: Schedule,
+    stats: bool,
+    heatmap: Option<Heatmap>,
+    queries: Vec<Query>,
+    render: Option<(Picture, Option<usize>)>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Option<Options>> {
+        if args.is_empty() {
+            return Ok(None);
+        }
+        let mut agents = None;
+        let mut starts = Vec::new();
+        let mut schedule = None;
+        let mut with_stats = false;
+        let mut heatmap = None;
+        let mut queries = Vec::new();
+        let mut render = false;
+        let mut out = None;
+        let mut labels = None;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("{flag} needs a value"));
+            match flag.as_str() {
+                "--agents" => {
+                    let raw = value()?;
+                    agents = Some(
+                        raw.parse::<usize>()
+                            .map_err(|e| anyhow!("Invalid --agents {raw:?}: {e}"))?,
+                    );
+                }
+                "--start" => {
+                    let start: Point<i32> = value()?.parse()?;
+                    if start.x.abs() > FURTHEST_START || start.y.abs() > FURTHEST_START {
+                        bail!("--start {start} is more than {FURTHEST_START} from 0,0");
+                    }
+                    starts.push(start);
+                }
+                "--schedule" => schedule = Some(value()?.parse()?),
+                "--stats" => with_stats = true,
+                "--heatmap" => heatmap = Some(value()?.parse()?),
+                "--query" => queries.push(value()?.parse()?),
+                "--render" => render = true,
+                "--out" => out = Some(value()?.parse()?),
+                "--labels" => {
+                    let raw = value()?;
+                    labels = Some(
+                        raw.parse::<usize>()
+                            .ok()
+                            .filter(|&every| every > 0)
+                            .ok_or_else(|| {
+                                anyhow!("Invalid --labels {raw:?}, it should be a number of moves")
+                            })?,
+                    );
+                }
+                _ => bail!("Unknown argument {flag:?}"),
+            }
+        }
+
+        // Robo-Santa joins Santa unless said otherwise
+        let agents = agents.unwrap_or(if starts.is_empty() { 2 } else { starts.len() });
+        if agents == 0 {
+            bail!("There has to be at least one agent");
+        }
+        if starts.is_empty() {
+            starts = vec![ORIGIN; agents];
+        } else if starts.len() != agents {
+            bail!(
+                "{} starts given for {agents} agents, give one each or none to start everyone at 0,0",
+                starts.len()
+            );
+        }
+
+        if !queries.is_empty() && (with_stats || heatmap.is_some() || render) {
+            bail!("--query can't be used with --stats, --heatmap or rendering");
+        }
+        let render = match (render, out, labels) {
+            (false, None, None) => None,
+            (false, ..) => bail!("--out and --labels only apply to rendering"),
+            (true, None, _) => bail!("Rendering needs an --out file"),
+            (true, _, _) if with_stats || heatmap.is_some() => {
+                bail!("--stats and --heatmap can't be used when rendering")
+            }
+            (true, Some(Picture::Ppm(_)), Some(_)) => bail!("--labels only apply to an SVG"),
+            (true, Some(picture), labels) => Some((picture, labels)),
+        };
+
+        Ok(Some(Options {
+            starts,
+            schedule: schedule.unwrap_or(Schedule::RoundRobin),
+            stats: with_stats,
+            heatmap,
+            queries,
+            render,
+        }))
+    }
+}
+
+/// `n` of `thing`, e.g. "1 house" or "3 houses".
+fn plural(n: usize, thing: &str) -> String {
+    if n == 1 {
+        format!("{n} {thing}")
+    } else {
+        format!("{n} {thing}s")
+    }
+}
+
+fn describe(deliveries: &Deliveries, starts: &[Point<i32>]) -> String {
+    let mut description = String::new();
+    for (agent, start) in starts.iter().enumerate() {
+        let _ = writeln!(
+            description,
+            "Agent {agent} from {start}: {}",
+            plural(deliveries.houses(agent), "house")
+        );
+    }
+    for a in 0..starts.len() {
+        for b in a + 1..starts.len() {
+            let _ = writeln!(
+                description,
+                "Agents {a} and {b} both visited: {}",
+                plural(deliveries.overlap(a, b), "house")
+            );
+        }
+    }
+    if starts.len() > 1 {
+        let _ = writeln!(
+            description,
+            "Visited by more than one agent: {}",
+            plural(deliveries.shared(), "house")
+        );
+    }
+    let _ = writeln!(description, "Distinct houses: {}", deliveries.distinct());
+    description
+}
+
+fn stats(deliveries: &Deliveries) -> String {
+    let mut stats = String::new();
+    if let Some(bounds) = deliveries.bounds() {
+        let (width, height) = bounds.span();
+        let _ = writeln!(
+            stats,
+            "Bounds: {} to {} ({width} x {height} houses)",
+            bounds.min, bounds.max
+        );
+    }
+    let _ = writeln!(stats, "Most presents:");
+    for (house, count) in deliveries.most_visited(TOP) {
+        let _ = writeln!(stats, "  {count} at {house}");
+    }
+    let _ = writeln!(stats, "Houses by presents:");
+    for (count, houses) in deliveries.histogram() {
+        let _ = writeln!(
+            stats,
+            "  {}: {}",
+            plural(count, "present"),
+            plural(houses, "house")
+        );
+    }
+    stats
+}
+
+/// A visit in words, e.g. "at move 12 by agent 1".
+fn when(visit: Visit) -> String {
+    match visit.step {
+        0 => format!("at the start by agent {}", visit.agent),
+        step => format!("at move {step} by agent {}", visit.agent),
+    }
+}
+
+fn answer(timeline: &Timeline, query: Query) -> String {
+    let mut answer = String::new();
+    match query {
+        Query::FirstVisit(house) => match timeline.first_visit(house) {
+            Some(visit) => {
+                let _ = writeln!(answer, "House {house} first visited {}", when(visit));
+            }
+            None => {
+                let _ = writeln!(answer, "House {house} never visited");
+            }
+        },
+        Query::History(house) => {
+            let visits = timeline.visits(house);
+            if visits.is_empty() {
+                let _ = writeln!(answer, "House {house} never visited");
+            } else {
+                let visits: Vec<String> = visits.iter().map(|&visit| when(visit)).collect();
+                let _ = writeln!(
+                    answer,
+                    "House {house} visited {}: {}",
+                    plural(visits.len(), "time"),
+                    visits.join(", ")
+                );
+            }
+        }
+        Query::VisitedOnce => {
+            let once: Vec<String> = timeline
+                .visited_once()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            let _ = write!(
+                answer,
+                "{} visited exactly once",
+                plural(once.len(), "house")
+            );
+            if once.is_empty() {
+                answer.push('\n');
+            } else {
+                let _ = writeln!(answer, ": {}", once.join(" "));
+            }
+        }
+        Query::Reach(n) => match timeline.moves_to_reach(n) {
+            Some(moves) => {
+                let _ = writeln!(
+                    answer,
+                    "{} reached after {}",
+                    plural(n, "distinct house"),
+                    plural(moves, "move")
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    answer,
+                    "Only {} ever reached, not {n}",
+                    plural(timeline.distinct_after(timeline.moves()), "distinct house")
+                );
+            }
+        },
+    }
+    answer
+}
+
+fn write(path: &std::path::Path, contents: &[u8], what: &str) -> Result<()> {
+    fs::write(path, contents).with_context(|| format!("Could not write {}", path.display()))?;
+    println!("{what} written to {}", path.display());
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Some(options) = Options::parse(&args)? {
+        let (starts, schedule) = (&options.starts, &options.schedule);
+        if !options.queries.is_empty() {
+            let timeline = delivery::timeline(PUZZLE_INPUT, starts, schedule)?;
+            for &query in &options.queries {
+                print!("{}", answer(&timeline, query));
+            }
+            return Ok(());
+        }
+
+        let deliveries = delivery::deliver(PUZZLE_INPUT, starts, schedule)?;
+        if let Some((picture, labels)) = options.render {
+            let routes = delivery::routes(PUZZLE_INPUT, starts, schedule)?;
+            return match picture {
+                Picture::Svg(path) => write(
+                    &path,
+                    draw::svg(&routes, &deliveries, labels).as_bytes(),
+                    "Routes",
+                ),
+                Picture::Ppm(path) => write(
+                    &path,
+                    &image::ppm(&draw::raster(&routes, &deliveries)?),
+                    "Routes",
+                ),
+            };
+        }
+
+        print!("{}", describe(&deliveries, starts));
+        if options.stats {
+            print!("{}", stats(&deliveries));
+        }
+        match options.heatmap {
+            Some(Heatmap::Ansi) => print!("{}", draw::ansi(&draw::heat(&deliveries)?)),
+            Some(Heatmap::Pgm(path)) => {
+                write(&path, &image::pgm(&draw::heat(&deliveries)?), "Heatmap")?;
+            }
+            Some(Heatmap::Ppm(path)) => {
+                let heat = draw::colour(&draw::heat(&deliveries)?);
+                write(&path, &image::ppm(&heat), "Heatmap")?;
+            }
+            None => {}
+        }
+        return Ok(());
+    }
+
+    let visited = count_houses_with_a_present(PUZZLE_INPUT)?;
+    let visited_part2 = count_houses_with_robo_santa(PUZZLE_INPUT)?;
+
+    println!("Part 1: {visited}");
+    println!("Part 2: {visited_part2}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_count_houses_with_a_present() -> Result<()> {
+        assert_eq!(count_houses_with_a_present(">")?, 2);
+        assert_eq!(count_houses_with_a_present("^>v<")?, 4);
+        assert_eq!(count_houses_with_a_present("^v^v^v^v^v")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_houses_with_robo_santa() -> Result<()> {
+        assert_eq!(count_houses_with_robo_santa("^v")?, 3);
+        assert_eq!(count_houses_with_robo_santa("^>v<")?, 3);
+        assert_eq!(count_houses_with_robo_santa("^v^v^v^v^v")?, 11);
+        Ok(())
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        assert_eq!(Options::parse(&[])?, None);
+        assert_eq!(
+            Options::parse(&args(&["--agents", "3"]))?,
+            Some(Options {
+                starts: vec![ORIGIN; 3],
+                schedule: Schedule::RoundRobin,
+                stats: false,
+                heatmap: None,
+                queries: vec![],
+                render: None,
+            })
+        );
+        assert_eq!(
+            Options::parse(&args(&[
+                "--start",
+                "1,2",
+                "--start",
+                "-3,4",
+                "--schedule",
+                "weighted=1,2",
+                "--query",
+                "first=1,-1",
+                "--query",
+                "reach=10"
+            ]))?,
+            Some(Options {
+                starts: vec![Point::new(1, 2), Point::new(-3, 4)],
+                schedule: Schedule::Weighted(vec![1, 2]),
+                stats: false,
+                heatmap: None,
+                queries: vec![Query::FirstVisit(Point::new(1, -1)), Query::Reach(10)],
+                render: None,
+            })
+        );
+
+        let Some(options) = Options::parse(&args(&["--stats", "--heatmap", "out/map.ppm"]))? else {
+            panic!("Options were given");
+        };
+        assert!(options.stats);
+        assert_eq!(options.heatmap, Some(Heatmap::Ppm("out/map.ppm".into())));
+
+        let Some(options) =
+            Options::parse(&args(&["--render", "--out", "a.svg", "--labels", "10"]))?
+        else {
+            panic!("Options were given");
+        };
+        assert_eq!(
+            options.render,
+            Some((Picture::Svg("a.svg".into()), Some(10)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let err = |list: &[&str]| Options::parse(&args(list)).unwrap_err().to_string();
+        assert_eq!(
+            err(&["--agents", "0"]),
+            "There has to be at least one agent"
+        );
+        assert_eq!(
+            err(&["--agents", "3", "--start", "0,0"]),
+            "1 starts given for 3 agents, give one each or none to start everyone at 0,0"
+        );
+        assert_eq!(err(&["--schedule"]), "--schedule needs a value");
+        assert_eq!(
+            err(&["--start", "-2000000000,0", "--start", "2000000000,0"]),
+            "--start -2000000000,0 is more than 1000000 from 0,0"
+        );
+        assert_eq!(err(&["--santas", "2"]), "Unknown argument \"--santas\"");
+        assert_eq!(
+            err(&["--heatmap", "map.png"]),
+            "Unknown heatmap \"map.png\", expected a .pgm or .ppm file, or ansi"
+        );
+        assert_eq!(err(&["--render"]), "Rendering needs an --out file");
+        assert_eq!(
+            err(&["--out", "a.svg"]),
+            "--out and --labels only apply to rendering"
+        );
+        assert_eq!(
+            err(&["--render", "--out", "a.ppm", "--labels", "5"]),
+            "--labels only apply to an SVG"
+        );
+        assert_eq!(
+            err(&["--render", "--out", "a.svg", "--stats"]),
+            "--stats and --heatmap can't be used when rendering"
+        );
+        assert!(
+            err(&["--render", "--out", "a.svg", "--labels", "0"]).starts_with("Invalid --labels")
+        );
+        assert_eq!(
+            err(&["--render", "--out", "a.png"]),
+            "Unknown picture \"a.png\", expected a .svg or .ppm file"
+        );
+        assert_eq!(
+            err(&["--query", "once", "--stats"]),
+            "--query can't be used with --stats, --heatmap or rendering"
+        );
+        assert!(err(&["--query", "reach=lots"]).starts_with("Invalid number of houses \"lots\""));
+        assert!(err(&["--query", "first=1"]).starts_with("Invalid point"));
+        assert!(err(&["--query", "last"]).starts_with("Unknown query \"last\""));
+    }
+
+    #[test]
+    fn test_describe() -> Result<()> {
+        let starts = [ORIGIN, ORIGIN];
+        let deliveries = delivery::deliver("^v^v", &starts, &Schedule::RoundRobin)?;
+        assert_eq!(
+            describe(&deliveries, &starts),
+            "Agent 0 from 0,0: 3 houses\n\
+             Agent 1 from 0,0: 3 houses\n\
+             Agents 0 and 1 both visited: 1 house\n\
+             Visited by more than one agent: 1 house\n\
+             Distinct houses: 5\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let deliveries = delivery::deliver("^v^v>", &[ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(
+            stats(&deliveries),
+            "Bounds: 0,-1 to 1,0 (2 x 2 houses)\n\
+             Most presents:\n  \
+             3 at 0,0\n  \
+             2 at 0,-1\n  \
+             1 at 1,0\n\
+             Houses by presents:\n  \
+             1 present: 1 house\n  \
+             2 presents: 1 house\n  \
+             3 presents: 1 house\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_answers() -> Result<()> {
+        let timeline = delivery::timeline("^>v>", &[ORIGIN, ORIGIN], &Schedule::RoundRobin)?;
+        let answer = |query: &str| answer(&timeline, query.parse().unwrap());
+
+        assert_eq!(
+            answer("first=0,0"),
+            "House 0,0 first visited at the start by agent 0\n"
+        );
+        assert_eq!(
+            answer("first=2,0"),
+            "House 2,0 first visited at move 4 by agent 1\n"
+        );
+        assert_eq!(answer("first=5,5"), "House 5,5 never visited\n");
+        assert_eq!(
+            answer("history=0,0"),
+            "House 0,0 visited 3 times: at the start by agent 0, at the start by agent 1, at move 3 by agent 0\n"
+        );
+        assert_eq!(answer("history=5,5"), "House 5,5 never visited\n");
+        assert_eq!(
+            answer("history=2,0"),
+            "House 2,0 visited 1 time: at move 4 by agent 1\n"
+        );
+        assert_eq!(
+            answer("once"),
+            "3 houses visited exactly once: 0,-1 1,0 2,0\n"
+        );
+        assert_eq!(
+            answer("reach=3"),
+            "3 distinct houses reached after 2 moves\n"
+        );
+        assert_eq!(
+            answer("reach=1"),
+            "1 distinct house reached after 0 moves\n"
+        );
+        assert_eq!(
+            answer("reach=10"),
+            "Only 4 distinct houses ever reached, not 10\n"
+        );
+        Ok(())
+    }
+}
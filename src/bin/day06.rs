@@ -43,132 +43,114 @@ toggle 0,0 through 999,999 would increase the total brightness by 2000000.
 
 #![warn(clippy::pedantic)]
 
-use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Result};
+use aoc2015::bitset::{BitSet, Bits};
+use aoc2015::geometry::{Point, Rect};
+use aoc2015::grid::Grid;
+use aoc2015::parse::{alt, complete, integer, literal, map, preceded, seq, Parser};
 
 const PUZZLE_INPUT: &str = include_str!("../inputs/day06.txt");
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Point {
-    x: u32,
-    y: u32,
+/// The light grid is 1000x1000, numbered from 0 to 999 in each direction.
+const SIZE: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Instruction {
+    TurnOn(Rect<usize>),
+    TurnOff(Rect<usize>),
+    Toggle(Rect<usize>),
 }
 
-impl Point {
-    fn parse(raw: &str) -> Result<Point> {
-        let mut parts = raw.split(',');
-        let x = parts.next().ok_or_else(|| anyhow!("No x"))?.parse()?;
-        let y = parts.next().ok_or_else(|| anyhow!("No y"))?.parse()?;
-        Ok(Point { x, y })
-    }
+/// An `x,y` corner.
+fn point<'a>() -> impl Parser<'a, Point<usize>> {
+    map(seq((integer(), literal(","), integer())), |(x, _, y)| {
+        Point::new(x, y)
+    })
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Instruction {
-    TurnOn { from: Point, to: Point },
-    TurnOff { from: Point, to: Point },
-    Toggle { from: Point, to: Point },
+/// The rectangle between two corners, `x,y through x,y`.
+fn rect<'a>() -> impl Parser<'a, Rect<usize>> {
+    map(
+        seq((point(), literal("through"), point())),
+        |(from, _, to)| Rect::new(from, to),
+    )
 }
 
 impl Instruction {
     fn parse(line: &str) -> Result<Instruction> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        match &parts[..] {
-            ["turn", "on", from, "through", to] => Ok(Instruction::TurnOn {
-                from: Point::parse(from)?,
-                to: Point::parse(to)?,
-            }),
-            ["turn", "off", from, "through", to] => Ok(Instruction::TurnOff {
-                from: Point::parse(from)?,
-                to: Point::parse(to)?,
-            }),
-            ["toggle", from, "through", to] => Ok(Instruction::Toggle {
-                from: Point::parse(from)?,
-                to: Point::parse(to)?,
-            }),
-            _ => Err(anyhow!("Unknown instruction: {}", line)),
+        let instruction = alt((
+            map(
+                preceded(seq((literal("turn"), literal("on"))), rect()),
+                Instruction::TurnOn,
+            ),
+            map(
+                preceded(seq((literal("turn"), literal("off"))), rect()),
+                Instruction::TurnOff,
+            ),
+            map(preceded(literal("toggle"), rect()), Instruction::Toggle),
+        ));
+        let instruction = complete(&instruction, line)
+            .map_err(|e| anyhow!("Invalid instruction {line:?}: {e}"))?;
+
+        // Both parts index straight into the grid, so check it fits once here
+        let rect = instruction.rect();
+        if rect.max.x >= SIZE || rect.max.y >= SIZE {
+            bail!(
+                "Invalid instruction {line:?}: {} through {} is off the {SIZE}x{SIZE} grid",
+                rect.min,
+                rect.max
+            );
         }
+        Ok(instruction)
     }
 
-    fn apply(&self, lights: &mut HashMap<Point, bool>) {
-        match self {
-            Instruction::TurnOn { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        lights.insert(Point { x, y }, true);
-                    }
-                }
-            }
-            Instruction::TurnOff { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        lights.insert(Point { x, y }, false);
-                    }
-                }
-            }
-            Instruction::Toggle { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        let point = Point { x, y };
-                        let current = lights.get(&point).unwrap_or(&false);
-                        lights.insert(point, !current);
-                    }
-                }
+    fn rect(&self) -> Rect<usize> {
+        let (Instruction::TurnOn(rect) | Instruction::TurnOff(rect) | Instruction::Toggle(rect)) =
+            self;
+        *rect
+    }
+
+    /// Part 1 keeps one bit per light, row by row, so each row of a rectangle is a
+    /// single range of bits.
+    fn apply(&self, lights: &mut BitSet) {
+        let rect = self.rect();
+        for y in rect.min.y..=rect.max.y {
+            let row = y * SIZE + rect.min.x..=y * SIZE + rect.max.x;
+            match self {
+                Instruction::TurnOn(_) => lights.insert_range(row),
+                Instruction::TurnOff(_) => lights.remove_range(row),
+                Instruction::Toggle(_) => lights.toggle_range(row),
             }
         }
     }
 
-    fn apply_part2(&self, lights: &mut HashMap<Point, u32>) {
+    fn apply_part2(&self, lights: &mut Grid<u32>) {
         match self {
-            Instruction::TurnOn { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        let point = Point { x, y };
-                        let current = lights.get(&point).unwrap_or(&0);
-                        lights.insert(point, current + 1);
-                    }
+            Instruction::TurnOn(rect) => {
+                for light in lights.view_mut(*rect).iter_mut() {
+                    *light += 1;
                 }
             }
-            Instruction::TurnOff { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        let point = Point { x, y };
-                        let current = lights.get(&point).unwrap_or(&0);
-                        lights.insert(point, current.saturating_sub(1));
-                    }
+            Instruction::TurnOff(rect) => {
+                for light in lights.view_mut(*rect).iter_mut() {
+                    *light = light.saturating_sub(1);
                 }
             }
-            Instruction::Toggle { from, to } => {
-                for x in from.x..=to.x {
-                    for y in from.y..=to.y {
-                        let point = Point { x, y };
-                        let current = lights.get(&point).unwrap_or(&0);
-                        lights.insert(point, current + 2);
-                    }
+            Instruction::Toggle(rect) => {
+                for light in lights.view_mut(*rect).iter_mut() {
+                    *light += 2;
                 }
             }
         }
     }
 }
 
-fn build_lights() -> HashMap<Point, bool> {
-    let mut lights = HashMap::new();
-    for x in 0..1000 {
-        for y in 0..1000 {
-            lights.insert(Point { x, y }, false);
-        }
-    }
-    lights
+fn build_lights() -> BitSet {
+    BitSet::with_capacity(SIZE * SIZE)
 }
 
-fn build_lights_part2() -> HashMap<Point, u32> {
-    let mut lights = HashMap::new();
-    for x in 0..1000 {
-        for y in 0..1000 {
-            lights.insert(Point { x, y }, 0);
-        }
-    }
-    lights
+fn build_lights_part2() -> Grid<u32> {
+    Grid::new(SIZE, SIZE, 0)
 }
 
 fn main() -> Result<()> {
@@ -177,7 +159,7 @@ fn main() -> Result<()> {
         let instruction = Instruction::parse(line)?;
         instruction.apply(&mut lights);
     }
-    let lit = lights.values().filter(|&&lit| lit).count();
+    let lit = lights.count();
 
     let mut lights2 = build_lights_part2();
     for line in PUZZLE_INPUT.trim().lines() {
@@ -185,7 +167,7 @@ fn main() -> Result<()> {
         instruction.apply_part2(&mut lights2);
     }
 
-    let brightness: u32 = lights2.values().sum();
+    let brightness: u32 = lights2.iter().sum();
 
     println!("Part 1: {lit}");
     println!("Part 2: {brightness}");
@@ -196,55 +178,90 @@ fn main() -> Result<()> {
 mod test {
     use super::*;
 
-    #[test]
-    fn test_point_parse() -> Result<()> {
-        assert_eq!(Point::parse("0,0")?, Point { x: 0, y: 0 });
-        assert_eq!(Point::parse("660,55")?, Point { x: 660, y: 55 });
-        Ok(())
-    }
-
     #[test]
     fn test_instruction_parse() -> Result<()> {
         assert_eq!(
             Instruction::parse("turn on 0,0 through 999,999")?,
-            Instruction::TurnOn {
-                from: Point { x: 0, y: 0 },
-                to: Point { x: 999, y: 999 }
-            }
+            Instruction::TurnOn(Rect::new(Point::new(0, 0), Point::new(999, 999)))
         );
         assert_eq!(
             Instruction::parse("toggle 0,0 through 999,0")?,
-            Instruction::Toggle {
-                from: Point { x: 0, y: 0 },
-                to: Point { x: 999, y: 0 }
-            }
+            Instruction::Toggle(Rect::new(Point::new(0, 0), Point::new(999, 0)))
         );
         assert_eq!(
             Instruction::parse("turn off 499,499 through 500,500")?,
-            Instruction::TurnOff {
-                from: Point { x: 499, y: 499 },
-                to: Point { x: 500, y: 500 }
-            }
+            Instruction::TurnOff(Rect::new(Point::new(499, 499), Point::new(500, 500)))
         );
         Ok(())
     }
 
+    #[test]
+    fn test_instruction_parse_errors() {
+        let err = Instruction::parse("turn up 0,0 through 9,9").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid instruction "turn up 0,0 through 9,9": expected `on` or `off` at column 6, found `up`"#
+        );
+
+        let err = Instruction::parse("toggle 0,0 thru 9,9").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid instruction "toggle 0,0 thru 9,9": expected `through` at column 12, found `thru`"#
+        );
+
+        // Off the edge would spill into the next row of bits in part 1, and panic in part 2
+        let err = Instruction::parse("turn on 990,0 through 1005,0").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid instruction "turn on 990,0 through 1005,0": 990,0 through 1005,0 is off the 1000x1000 grid"#
+        );
+        assert!(Instruction::parse("toggle 0,1000 through 0,0").is_err());
+        assert!(Instruction::parse("turn off 999,999 through 999,999").is_ok());
+
+        // Keywords run together with something else aren't keywords
+        let err = Instruction::parse("turnon 0,0 through 1,1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid instruction "turnon 0,0 through 1,1": expected `turn` or `toggle` at column 1, found `turnon`"#
+        );
+        assert!(Instruction::parse("togglex 0,0 through 1,1").is_err());
+        assert!(Instruction::parse("toggle 0,0 throughout 1,1").is_err());
+    }
+
     #[test]
     fn test_instruction_apply() -> Result<()> {
         let mut lights = build_lights();
         Instruction::parse("turn on 0,0 through 999,999")?.apply(&mut lights);
-        assert_eq!(lights.len(), 1_000_000);
-        assert_eq!(lights.values().filter(|&&v| v).count(), 1_000_000);
+        assert_eq!(lights.capacity(), 1_000_000);
+        assert_eq!(lights.count(), 1_000_000);
 
         let mut lights = build_lights();
         Instruction::parse("toggle 0,0 through 999,0")?.apply(&mut lights);
-        assert_eq!(lights.len(), 1_000_000);
-        assert_eq!(lights.values().filter(|&&v| v).count(), 1_000);
+        assert_eq!(lights.capacity(), 1_000_000);
+        assert_eq!(lights.count(), 1_000);
 
         let mut lights = build_lights();
         Instruction::parse("turn off 499,499 through 500,500")?.apply(&mut lights);
-        assert_eq!(lights.len(), 1_000_000);
-        assert_eq!(lights.values().filter(|&&v| v).count(), 0);
+        assert_eq!(lights.capacity(), 1_000_000);
+        assert_eq!(lights.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_instruction_apply_part2() -> Result<()> {
+        let mut lights = build_lights_part2();
+        Instruction::parse("turn on 0,0 through 0,0")?.apply_part2(&mut lights);
+        assert_eq!(lights.iter().sum::<u32>(), 1);
+
+        let mut lights = build_lights_part2();
+        Instruction::parse("toggle 0,0 through 999,999")?.apply_part2(&mut lights);
+        assert_eq!(lights.iter().sum::<u32>(), 2_000_000);
+
+        Instruction::parse("turn off 0,0 through 999,999")?.apply_part2(&mut lights);
+        Instruction::parse("turn off 0,0 through 999,999")?.apply_part2(&mut lights);
+        Instruction::parse("turn off 0,0 through 999,999")?.apply_part2(&mut lights);
+        assert_eq!(lights.iter().sum::<u32>(), 0);
 
         Ok(())
     }
@@ -49,88 +49,143 @@ In little Bobby's kit's instructions booklet (provided as your puzzle input), wh
 #![warn(clippy::pedantic)]
 
 use anyhow::{anyhow, Result};
+use aoc2015::parse::{alt, complete, identifier, integer, literal, map, seq, Parser};
 use std::collections::HashMap;
 
 const PUZZLE_INPUT: &str = include_str!("../inputs/day07.txt");
 
+/// One input to a gate, either a fixed signal or another wire.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Operand<'a> {
+    Value(u16),
+    Wire(&'a str),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Gate<'a> {
+    Direct(Operand<'a>),
+    Not(Operand<'a>),
+    And(Operand<'a>, Operand<'a>),
+    Or(Operand<'a>, Operand<'a>),
+    LShift(Operand<'a>, Operand<'a>),
+    RShift(Operand<'a>, Operand<'a>),
+}
+
+/// A gate feeding its output to a wire, e.g. `x AND y -> d`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Connection<'a> {
+    gate: Gate<'a>,
+    wire: &'a str,
+}
+
+fn operand<'a>() -> impl Parser<'a, Operand<'a>> {
+    alt((
+        map(integer(), Operand::Value),
+        map(identifier(), Operand::Wire),
+    ))
+}
+
+/// `x OP y -> wire`
+fn binary<'a>(
+    op: &'static str,
+    gate: fn(Operand<'a>, Operand<'a>) -> Gate<'a>,
+) -> impl Parser<'a, Connection<'a>> {
+    map(
+        seq((
+            operand(),
+            literal(op),
+            operand(),
+            literal("->"),
+            identifier(),
+        )),
+        move |(x, _, y, _, wire)| Connection {
+            gate: gate(x, y),
+            wire,
+        },
+    )
+}
+
+impl<'a> Connection<'a> {
+    fn parse(line: &'a str) -> Result<Self> {
+        // Each alternative is a whole line, so a bad op reports every op it could have been
+        let connection = alt((
+            map(
+                seq((literal("NOT"), operand(), literal("->"), identifier())),
+                |(_, x, _, wire)| Connection {
+                    gate: Gate::Not(x),
+                    wire,
+                },
+            ),
+            binary("AND", Gate::And),
+            binary("OR", Gate::Or),
+            binary("LSHIFT", Gate::LShift),
+            binary("RSHIFT", Gate::RShift),
+            map(
+                seq((operand(), literal("->"), identifier())),
+                |(x, _, wire)| Connection {
+                    gate: Gate::Direct(x),
+                    wire,
+                },
+            ),
+        ));
+        complete(&connection, line).map_err(|e| anyhow!("Invalid signal {line:?}: {e}"))
+    }
+}
+
 /// This took a lot of attempts and thinking!
 fn solve(input: &str, wire: &str, override_b: bool) -> Result<u16> {
-    let mut signals: Vec<&str> = input.trim().lines().collect();
+    let mut connections: Vec<Connection> = input
+        .trim()
+        .lines()
+        .map(Connection::parse)
+        .collect::<Result<_>>()?;
 
     let mut results: HashMap<&str, u16> = HashMap::new();
 
-    while !signals.is_empty() {
-        // Queue for signals that couldn't be processed yet
-        let mut todo: Vec<&str> = Vec::new();
-
-        for signal in signals {
-            let parts: Vec<&str> = signal.split_whitespace().collect();
-
-            match parts.as_slice() {
-                [value, "->", wire] => {
-                    // Just sending a value to a wire
-                    if let Ok(x) = value.parse() {
-                        // The value is already a u16, can just insert it straight
-                        // into results
-                        if override_b && wire == &"b" {
-                            // Override the value of b with the answer from part 1
-                            results.insert(wire, 16076);
-                            continue;
-                        }
-                        results.insert(wire, x);
-                    } else if let Some(x) = results.get(value) {
-                        // The value is a signal that has already been processed
-                        results.insert(wire, *x);
-                    } else {
-                        // The value is a signal that hasn't been processed yet
-                        todo.push(signal);
-                    }
-                }
-                ["NOT", value, "->", wire] => {
-                    // NOT gate
-                    if let Some(x) = results.get(value) {
-                        // We're "notting" a signal that has already been processed
-                        results.insert(wire, !x);
-                    } else {
-                        // Not been processed yet
-                        todo.push(signal);
-                    }
+    while !connections.is_empty() {
+        // Queue for connections that couldn't be processed yet
+        let mut todo: Vec<Connection> = Vec::new();
+        let waiting = connections.len();
+
+        for connection in connections {
+            if override_b && connection.wire == "b" {
+                if let Gate::Direct(Operand::Value(_)) = connection.gate {
+                    // Override the value of b with the answer from part 1
+                    results.insert("b", 16076);
+                    continue;
                 }
-                [left, op, right, "->", wire] => {
-                    if let Ok(x) = left.parse() {
-                        // Left is already a u16
-                        results.insert(left, x);
-                    }
-                    if let Ok(x) = right.parse() {
-                        // Right is already a u16
-                        results.insert(right, x);
-                    }
-
-                    if let Some(x) = results.get(left) {
-                        if let Some(y) = results.get(right) {
-                            // Both left and right have been processed
-                            match *op {
-                                "AND" => results.insert(wire, x & y),
-                                "OR" => results.insert(wire, x | y),
-                                "LSHIFT" => results.insert(wire, x << y),
-                                "RSHIFT" => results.insert(wire, x >> y),
-                                _ => unreachable!("Invalid op: {op}"),
-                            };
-                        } else {
-                            // Left has been processed, right hasn't
-                            todo.push(signal);
-                        }
-                    } else {
-                        // Left hasn't been processed
-                        todo.push(signal);
-                    }
+            }
+
+            // A fixed value, or a wire that has already been processed
+            let signal = |operand: Operand| match operand {
+                Operand::Value(x) => Some(x),
+                Operand::Wire(wire) => results.get(wire).copied(),
+            };
+
+            let output = match connection.gate {
+                Gate::Direct(x) => signal(x),
+                Gate::Not(x) => signal(x).map(|x| !x),
+                Gate::And(x, y) => signal(x).zip(signal(y)).map(|(x, y)| x & y),
+                Gate::Or(x, y) => signal(x).zip(signal(y)).map(|(x, y)| x | y),
+                Gate::LShift(x, y) => signal(x).zip(signal(y)).map(|(x, y)| x << y),
+                Gate::RShift(x, y) => signal(x).zip(signal(y)).map(|(x, y)| x >> y),
+            };
+
+            match output {
+                Some(output) => {
+                    results.insert(connection.wire, output);
                 }
-                _ => unreachable!("Invalid signal: {signal}"),
+                // Inputs not all processed yet
+                None => todo.push(connection),
             }
         }
 
-        // Process the todo signals next time round
-        signals = todo;
+        if todo.len() == waiting {
+            return Err(anyhow!("{waiting} signals can never be resolved"));
+        }
+
+        // Process the todo connections next time round
+        connections = todo;
     }
 
     let answer = results
@@ -175,4 +230,36 @@ NOT y -> i";
 
         Ok(())
     }
+
+    #[test]
+    fn test_connection_parse() -> Result<()> {
+        assert_eq!(
+            Connection::parse("123 -> x")?,
+            Connection {
+                gate: Gate::Direct(Operand::Value(123)),
+                wire: "x"
+            }
+        );
+        assert_eq!(
+            Connection::parse("x LSHIFT 2 -> f")?,
+            Connection {
+                gate: Gate::LShift(Operand::Wire("x"), Operand::Value(2)),
+                wire: "f"
+            }
+        );
+        assert_eq!(
+            Connection::parse("NOT y -> i")?,
+            Connection {
+                gate: Gate::Not(Operand::Wire("y")),
+                wire: "i"
+            }
+        );
+
+        let err = Connection::parse("x XOR y -> z").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid signal "x XOR y -> z": expected `AND`, `OR`, `LSHIFT`, `RSHIFT` or `->` at column 3, found `XOR`"#
+        );
+        Ok(())
+    }
 }
@@ -93,7 +93,7 @@ fn encode(s: &str) -> String {
     for c in s.chars() {
         match c {
             '"' => encoded.push_str(r#"\""#),
-            '\\' => encoded.push_str(r#"\\"#),
+            '\\' => encoded.push_str(r"\\"),
             c => encoded.push(c),
         }
     }
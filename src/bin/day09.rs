@@ -41,9 +41,9 @@ What is the distance of the longest route?
 #![warn(clippy::pedantic)]
 
 use anyhow::{anyhow, Result};
-
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use aoc2015::combinatorics::try_for_each_path;
+use aoc2015::graph::{Graph, NodeId};
+use aoc2015::parse::{complete, identifier, integer, literal, seq};
 
 const PUZZLE_INPUT: &str = include_str!("../inputs/day09.txt");
 
@@ -56,17 +56,25 @@ struct Leg<'a> {
 
 impl<'a> Leg<'a> {
     fn parse(line: &'a str) -> Result<Self> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        match parts.as_slice() {
-            [from, "to", to, "=", distance] => {
-                let distance = distance.parse()?;
-                Ok(Self { from, to, distance })
-            }
-            _ => Err(anyhow!("Invalid line: {line}")),
-        }
+        let leg = seq((
+            identifier(),
+            literal("to"),
+            identifier(),
+            literal("="),
+            integer(),
+        ));
+        let (from, _, to, _, distance) =
+            complete(&leg, line).map_err(|e| anyhow!("Invalid line {line:?}: {e}"))?;
+        Ok(Self { from, to, distance })
     }
 }
 
+/// Render a route by name, e.g. `London -> Dublin -> Belfast`.
+fn describe(distances: &Graph<&str, u32>, path: &[NodeId]) -> String {
+    let names: Vec<&str> = path.iter().map(|&id| *distances.name(id)).collect();
+    names.join(" -> ")
+}
+
 #[derive(Debug)]
 struct Solution {
     shortest: u32,
@@ -74,30 +82,29 @@ struct Solution {
 }
 
 fn solve(input: &str) -> Result<Solution> {
-    let mut distances: HashMap<(&str, &str), u32> = HashMap::new();
-    let mut locations: HashSet<&str> = HashSet::new();
+    let mut distances: Graph<&str, u32> = Graph::undirected();
 
     for line in input.trim().lines() {
         let leg = Leg::parse(line)?;
-        distances.insert((leg.from, leg.to), leg.distance);
-        distances.insert((leg.to, leg.from), leg.distance);
-        locations.insert(leg.from);
-        locations.insert(leg.to);
+        distances.add_edge(leg.from, leg.to, leg.distance);
     }
 
     let mut shortest_distance: Option<u32> = None;
     let mut longest_distance: Option<u32> = None;
 
-    for permutation in locations.iter().permutations(locations.len()).unique() {
-        let mut distance = 0;
-        for (from, to) in permutation.iter().tuple_windows() {
-            distance += distances
-                .get(&(**from, **to))
-                .ok_or_else(|| anyhow!("No distance found for leg {from} -> {to}"))?;
-        }
+    // Every leg is the same both ways, so a route and its reverse are the same length
+    let mut locations: Vec<NodeId> = distances.nodes().collect();
+    try_for_each_path(&mut locations, |route| -> Result<()> {
+        let distance = distances.path_weight(route).ok_or_else(|| {
+            anyhow!(
+                "No distance found for route {}, or it's too long to add up",
+                describe(&distances, route)
+            )
+        })?;
         shortest_distance = Some(shortest_distance.map_or(distance, |d| d.min(distance)));
         longest_distance = Some(longest_distance.map_or(distance, |d| d.max(distance)));
-    }
+        Ok(())
+    })?;
 
     Ok(Solution {
         shortest: shortest_distance.ok_or_else(|| anyhow!("No shortest distance found"))?,
@@ -151,6 +158,12 @@ Dublin to Belfast = 141";
         Ok(())
     }
 
+    #[test]
+    fn test_missing_leg() {
+        let err = solve("London to Dublin = 464\nDublin to Belfast = 141").unwrap_err();
+        assert!(err.to_string().starts_with("No distance found for route"));
+    }
+
     #[test]
     fn test_shortest_route_example_1() -> Result<()> {
         assert_eq!(solve(TEST_INPUT)?.shortest, 605);
@@ -0,0 +1,482 @@
+//! Packing items into as few equal bins as possible, e.g. cutting pieces from fixed size rolls.
+//!
+//! First fit decreasing is quick and never more than about 22% over the best, and a
+//! Martello-Toth lower bound often proves it optimal outright. When it can't, small inputs
+//! get a branch and bound search for the true minimum.
+
+use std::error::Error;
+use std::fmt;
+
+/// The most items [`pack`] will search exactly when first fit decreasing isn't provably best.
+const EXACT_ITEMS: usize = 60;
+
+/// How many nodes the exact search explores before settling for the best packing so far.
+const NODE_BUDGET: u64 = 2_000_000;
+
+/// An item bigger than a whole bin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Oversized {
+    pub index: usize,
+    pub size: u64,
+    pub capacity: u64,
+}
+
+impl fmt::Display for Oversized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "item {} needs {}, more than a whole bin of {}",
+            self.index, self.size, self.capacity
+        )
+    }
+}
+
+impl Error for Oversized {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    FirstFitDecreasing,
+    Exact,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packing {
+    /// The item indices in each bin, in increasing order
+    pub bins: Vec<Vec<usize>>,
+    /// No packing can use fewer bins than this
+    pub lower_bound: usize,
+    pub method: Method,
+    /// Whether no packing could use fewer bins
+    pub optimal: bool,
+}
+
+impl Packing {
+    /// How much of each bin its items use.
+    #[must_use]
+    pub fn used(&self, sizes: &[u64]) -> Vec<u64> {
+        self.bins
+            .iter()
+            .map(|bin| bin.iter().map(|&i| sizes[i]).sum())
+            .collect()
+    }
+
+    /// The capacity left over across every bin, or `None` if that's too much for a `u64`.
+    #[must_use]
+    pub fn waste(&self, sizes: &[u64], capacity: u64) -> Option<u64> {
+        self.used(sizes)
+            .iter()
+            .try_fold(0u64, |waste, used| waste.checked_add(capacity - used))
+    }
+}
+
+fn check(sizes: &[u64], capacity: u64) -> Result<(), Oversized> {
+    match sizes.iter().position(|&size| size > capacity) {
+        Some(index) => Err(Oversized {
+            index,
+            size: sizes[index],
+            capacity,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Item indices biggest first, ties in input order.
+fn decreasing(sizes: &[u64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]).then(a.cmp(&b)));
+    order
+}
+
+/// No packing of `sizes` can use fewer bins than this.
+///
+/// The Martello-Toth L2 bound: for each threshold `k` up to half a bin, items bigger than
+/// `capacity - k` each need a bin of their own, as do items over half a bin, and the items
+/// of at least `k` left over need at least their total size worth of bins beyond what
+/// fits around the half bin items.
+#[must_use]
+pub fn lower_bound(sizes: &[u64], capacity: u64) -> usize {
+    // Items of size 0 still need a bin to go in, and they're all that fit in an empty one
+    let at_least = usize::from(!sizes.is_empty());
+    if capacity == 0 {
+        return at_least;
+    }
+
+    // Totals of many big items can be more than a u64 holds
+    let wide = u128::from(capacity);
+    let total: u128 = sizes.iter().copied().map(u128::from).sum();
+    let mut best = usize::try_from(total.div_ceil(wide))
+        .unwrap_or(usize::MAX)
+        .max(at_least);
+
+    let mut thresholds: Vec<u64> = sizes
+        .iter()
+        .copied()
+        .filter(|&size| size <= capacity / 2)
+        .collect();
+    thresholds.push(0);
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    for k in thresholds {
+        let (mut alone, mut halves, mut halves_size, mut small_size) = (0, 0, 0, 0);
+        for &size in sizes {
+            if size > capacity - k {
+                alone += 1;
+            } else if size > capacity / 2 {
+                halves += 1;
+                halves_size += u128::from(size);
+            } else if size >= k {
+                small_size += u128::from(size);
+            }
+        }
+        let room_beside_halves = halves * wide - halves_size;
+        let extra = small_size.saturating_sub(room_beside_halves).div_ceil(wide);
+        let bound = alone + halves + extra;
+        best = best.max(usize::try_from(bound).unwrap_or(usize::MAX));
+    }
+
+    best
+}
+
+/// Put each item, biggest first, into the first bin it fits in.
+///
+/// # Errors
+///
+/// If any item is bigger than a bin.
+pub fn first_fit_decreasing(sizes: &[u64], capacity: u64) -> Result<Packing, Oversized> {
+    check(sizes, capacity)?;
+
+    let mut bins: Vec<Vec<usize>> = Vec::new();
+    let mut room: Vec<u64> = Vec::new();
+    for i in decreasing(sizes) {
+        if let Some(bin) = room.iter().position(|&r| r >= sizes[i]) {
+            room[bin] -= sizes[i];
+            bins[bin].push(i);
+        } else {
+            room.push(capacity - sizes[i]);
+            bins.push(vec![i]);
+        }
+    }
+
+    Ok(finish(
+        bins,
+        lower_bound(sizes, capacity),
+        Method::FirstFitDecreasing,
+        false,
+    ))
+}
+
+fn finish(mut bins: Vec<Vec<usize>>, lower_bound: usize, method: Method, proven: bool) -> Packing {
+    for bin in &mut bins {
+        bin.sort_unstable();
+    }
+    bins.sort();
+    Packing {
+        optimal: proven || bins.len() == lower_bound,
+        bins,
+        lower_bound,
+        method,
+    }
+}
+
+/// The fewest bins possible, by branch and bound over which bin each item goes in, biggest
+/// first. Gives up after `budget` nodes with the best packing found, which is never worse
+/// than first fit decreasing.
+///
+/// # Errors
+///
+/// If any item is bigger than a bin.
+pub fn exact(sizes: &[u64], capacity: u64, budget: u64) -> Result<Packing, Oversized> {
+    let start = first_fit_decreasing(sizes, capacity)?;
+    if start.optimal {
+        return Ok(start);
+    }
+
+    let order = decreasing(sizes);
+    let remaining: Vec<u128> = (0..=order.len())
+        .map(|depth| order[depth..].iter().map(|&i| u128::from(sizes[i])).sum())
+        .collect();
+    let mut search = Search {
+        sizes,
+        capacity,
+        order,
+        remaining,
+        room: Vec::new(),
+        assignment: Vec::new(),
+        best_bins: start.bins.len(),
+        best: None,
+        lower_bound: start.lower_bound,
+        nodes: 0,
+        budget,
+    };
+    search.explore(0);
+
+    let finished = search.nodes <= search.budget;
+    let Some(assignment) = search.best else {
+        return Ok(Packing {
+            optimal: finished,
+            ..start
+        });
+    };
+
+    let mut bins = vec![Vec::new(); search.best_bins];
+    for (depth, &bin) in assignment.iter().enumerate() {
+        bins[bin].push(search.order[depth]);
+    }
+    Ok(finish(bins, start.lower_bound, Method::Exact, finished))
+}
+
+/// The best packing available at a reasonable cost: first fit decreasing, then an exact
+/// search if that isn't provably optimal and there are few enough items.
+///
+/// # Errors
+///
+/// If any item is bigger than a bin.
+pub fn pack(sizes: &[u64], capacity: u64) -> Result<Packing, Oversized> {
+    if sizes.len() <= EXACT_ITEMS {
+        exact(sizes, capacity, NODE_BUDGET)
+    } else {
+        first_fit_decreasing(sizes, capacity)
+    }
+}
+
+struct Search<'a> {
+    sizes: &'a [u64],
+    capacity: u64,
+    /// Item indices biggest first, the order they're placed in
+    order: Vec<usize>,
+    /// The total size of the items from each depth on, wide enough not to overflow
+    remaining: Vec<u128>,
+    /// Room left in each open bin
+    room: Vec<u64>,
+    /// The bin of the item at each depth so far
+    assignment: Vec<usize>,
+    best_bins: usize,
+    best: Option<Vec<usize>>,
+    lower_bound: usize,
+    nodes: u64,
+    budget: u64,
+}
+
+impl Search<'_> {
+    fn explore(&mut self, depth: usize) {
+        self.nodes += 1;
+        if self.nodes > self.budget || self.best_bins == self.lower_bound {
+            return;
+        }
+        if depth == self.order.len() {
+            self.best_bins = self.room.len();
+            self.best = Some(self.assignment.clone());
+            return;
+        }
+
+        // Even filling every gap, whatever's left over needs this many more bins
+        let free: u128 = self.room.iter().copied().map(u128::from).sum();
+        let more = self.remaining[depth]
+            .saturating_sub(free)
+            .div_ceil(u128::from(self.capacity));
+        if self.room.len() + usize::try_from(more).unwrap_or(usize::MAX) >= self.best_bins {
+            return;
+        }
+
+        let size = self.sizes[self.order[depth]];
+        for bin in 0..self.room.len() {
+            // Bins with the same room left are interchangeable, only try the first
+            let room = self.room[bin];
+            if room < size || self.room[..bin].contains(&room) {
+                continue;
+            }
+            self.room[bin] -= size;
+            self.assignment.push(bin);
+            self.explore(depth + 1);
+            self.assignment.pop();
+            self.room[bin] += size;
+        }
+
+        if self.room.len() + 1 < self.best_bins {
+            self.room.push(self.capacity - size);
+            self.assignment.push(self.room.len() - 1);
+            self.explore(depth + 1);
+            self.assignment.pop();
+            self.room.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::combinatorics::subsets;
+    use crate::rng::XorShift;
+
+    fn valid(sizes: &[u64], capacity: u64, packing: &Packing) -> bool {
+        let mut seen: Vec<usize> = packing.bins.iter().flatten().copied().collect();
+        seen.sort_unstable();
+        seen == (0..sizes.len()).collect::<Vec<_>>()
+            && packing.used(sizes).iter().all(|&used| used <= capacity)
+    }
+
+    /// The fewest bins by brute force over subsets: the best packing of a set of items is
+    /// the best packing of everything but some bin's worth containing the first item, plus 1.
+    fn fewest_bins(sizes: &[u64], capacity: u64) -> usize {
+        let n = u32::try_from(sizes.len()).unwrap();
+        let fits: Vec<bool> = subsets(n)
+            .map(|mask| {
+                let total: u64 = (0..sizes.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| sizes[i])
+                    .sum();
+                total <= capacity
+            })
+            .collect();
+
+        let mut fewest = vec![usize::MAX; fits.len()];
+        fewest[0] = 0;
+        for mask in 1..fits.len() {
+            let lowest = mask & mask.wrapping_neg();
+            // Every sub-mask of `mask` that includes its lowest item
+            let rest = mask ^ lowest;
+            let mut sub = rest;
+            loop {
+                let bin = sub | lowest;
+                if fits[bin] && fewest[mask ^ bin] != usize::MAX {
+                    fewest[mask] = fewest[mask].min(fewest[mask ^ bin] + 1);
+                }
+                if sub == 0 {
+                    break;
+                }
+                sub = (sub - 1) & rest;
+            }
+        }
+        fewest[fits.len() - 1]
+    }
+
+    #[test]
+    fn test_first_fit_decreasing() -> Result<(), Oversized> {
+        let sizes = [2, 5, 4, 7, 1, 3, 8];
+        let packing = first_fit_decreasing(&sizes, 10)?;
+        assert!(valid(&sizes, 10, &packing));
+        assert_eq!(packing.bins.len(), 3);
+        assert_eq!(packing.lower_bound, 3);
+        assert!(packing.optimal);
+        assert_eq!(packing.waste(&sizes, 10), Some(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_beats_first_fit_decreasing() -> Result<(), Oversized> {
+        // FFD puts 5 and 4 together, then can't pair the 3s and 2s up neatly
+        let sizes = [5, 4, 4, 3, 3, 3, 2, 2, 2, 2];
+        let greedy = first_fit_decreasing(&sizes, 10)?;
+        let best = exact(&sizes, 10, u64::MAX)?;
+        assert_eq!(best.bins.len(), fewest_bins(&sizes, 10));
+        assert!(best.bins.len() <= greedy.bins.len());
+        assert!(best.optimal);
+        assert!(valid(&sizes, 10, &best));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lower_bound() {
+        // Three items over half a bin can't share
+        assert_eq!(lower_bound(&[6, 6, 6, 1], 10), 3);
+        // Total size
+        assert_eq!(lower_bound(&[5, 5, 5, 5, 5], 10), 3);
+        // Beside items of 6, only a 4 fits, so 5s need bins of their own
+        assert_eq!(lower_bound(&[6, 6, 5, 5, 5], 10), 4);
+        assert_eq!(lower_bound(&[], 10), 0);
+    }
+
+    #[test]
+    fn test_items_of_size_zero() -> Result<(), Oversized> {
+        for capacity in [0, 10] {
+            assert_eq!(lower_bound(&[0, 0, 0], capacity), 1);
+            let best = exact(&[0, 0, 0], capacity, 1000)?;
+            assert_eq!(best.bins, vec![vec![0, 1, 2]]);
+            assert!(best.optimal);
+        }
+        assert_eq!(lower_bound(&[0, 5], 10), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sizes_near_the_top_of_u64() -> Result<(), Oversized> {
+        // The same items as above scaled up, so their total is more than a u64 holds
+        let scale = u64::MAX / 10;
+        let sizes: Vec<u64> = [5, 4, 4, 3, 3, 3, 2, 2, 2, 2]
+            .iter()
+            .map(|size| size * scale)
+            .collect();
+        let capacity = 10 * scale;
+        assert_eq!(lower_bound(&sizes, capacity), 3);
+
+        let best = exact(&sizes, capacity, u64::MAX)?;
+        assert_eq!(best.bins.len(), 3);
+        assert!(valid(&sizes, capacity, &best));
+        assert_eq!(best.waste(&sizes, capacity), Some(0));
+
+        // Three bins with nearly half left in each wastes more than a u64
+        let halves = [u64::MAX / 2 + 1; 3];
+        let packing = pack(&halves, u64::MAX)?;
+        assert_eq!(packing.bins.len(), 3);
+        assert_eq!(packing.waste(&halves, u64::MAX), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized() {
+        let err = pack(&[3, 11, 2], 10).unwrap_err();
+        assert_eq!(
+            err,
+            Oversized {
+                index: 1,
+                size: 11,
+                capacity: 10
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "item 1 needs 11, more than a whole bin of 10"
+        );
+    }
+
+    #[test]
+    fn test_generated_inputs() -> Result<(), Oversized> {
+        let mut rng = XorShift::new(2015);
+        let mut improved = 0;
+        for _ in 0..300 {
+            let capacity = rng.between(10..=100);
+            let sizes: Vec<u64> = (0..rng.between(1..=12))
+                .map(|_| rng.between(1..=capacity))
+                .collect();
+
+            let greedy = first_fit_decreasing(&sizes, capacity)?;
+            let best = exact(&sizes, capacity, u64::MAX)?;
+            let fewest = fewest_bins(&sizes, capacity);
+
+            assert!(valid(&sizes, capacity, &greedy));
+            assert!(valid(&sizes, capacity, &best));
+            assert!(best.optimal);
+            assert_eq!(best.bins.len(), fewest, "{sizes:?} in {capacity}");
+            assert!(greedy.lower_bound <= fewest);
+            // FFD's worst case, FFD <= 11/9 OPT + 6/9
+            assert!(9 * greedy.bins.len() <= 11 * fewest + 6);
+            if greedy.bins.len() > fewest {
+                improved += 1;
+            }
+        }
+        // Some of those should have needed the exact search
+        assert!(improved > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_budget_keeps_a_valid_packing() -> Result<(), Oversized> {
+        let mut rng = XorShift::new(9);
+        let sizes: Vec<u64> = (0..50).map(|_| rng.between(20..=60)).collect();
+        let packing = exact(&sizes, 100, 10)?;
+        assert!(valid(&sizes, 100, &packing));
+        assert!(packing.bins.len() >= packing.lower_bound);
+        Ok(())
+    }
+}
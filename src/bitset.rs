@@ -0,0 +1,460 @@
+//! Sets of small integers stored one bit each.
+//!
+//! [`BitArray`] has a fixed capacity chosen at compile time and is `Copy`, so it suits
+//! things like a set of visited cities. [`BitSet`] lives on the heap and grows as bits
+//! are added. Both get their operations from the [`Bits`] trait, whose range operations
+//! work a whole 64-bit word at a time rather than bit by bit.
+
+use std::ops::{Bound, Range, RangeBounds};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Set operations over anything stored as a slice of 64-bit words, bit `i` of the set
+/// being bit `i % 64` of word `i / 64`.
+pub trait Bits {
+    fn words(&self) -> &[u64];
+
+    fn words_mut(&mut self) -> &mut [u64];
+
+    /// Make room for bits `0..bits`.
+    ///
+    /// # Panics
+    ///
+    /// If the set can't grow that large.
+    fn grow_to(&mut self, bits: usize);
+
+    /// The number of bits that can be stored without growing.
+    fn capacity(&self) -> usize {
+        self.words().len() * WORD
+    }
+
+    fn contains(&self, bit: usize) -> bool {
+        self.words()
+            .get(bit / WORD)
+            .is_some_and(|word| word & (1 << (bit % WORD)) != 0)
+    }
+
+    /// Add `bit`, returning whether it was newly added.
+    ///
+    /// # Panics
+    ///
+    /// If `bit` doesn't fit, see [`Bits::grow_to`].
+    fn insert(&mut self, bit: usize) -> bool {
+        self.grow_to(bit + 1);
+        let word = &mut self.words_mut()[bit / WORD];
+        let mask = 1 << (bit % WORD);
+        let added = *word & mask == 0;
+        *word |= mask;
+        added
+    }
+
+    /// Take `bit` out, returning whether it was there.
+    fn remove(&mut self, bit: usize) -> bool {
+        let Some(word) = self.words_mut().get_mut(bit / WORD) else {
+            return false;
+        };
+        let mask = 1 << (bit % WORD);
+        let removed = *word & mask != 0;
+        *word &= !mask;
+        removed
+    }
+
+    /// Flip `bit`, returning whether it's now set.
+    ///
+    /// # Panics
+    ///
+    /// If `bit` doesn't fit, see [`Bits::grow_to`].
+    fn toggle(&mut self, bit: usize) -> bool {
+        self.grow_to(bit + 1);
+        let word = &mut self.words_mut()[bit / WORD];
+        *word ^= 1 << (bit % WORD);
+        *word & (1 << (bit % WORD)) != 0
+    }
+
+    /// Add every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range is unbounded above, or doesn't fit, see [`Bits::grow_to`].
+    fn insert_range(&mut self, range: impl RangeBounds<usize>) {
+        let range = resolve(&range, None);
+        self.grow_to(range.end);
+        let words = self.words_mut();
+        for_each_word(range, |index, mask| words[index] |= mask);
+    }
+
+    /// Take out every bit in `range`.
+    fn remove_range(&mut self, range: impl RangeBounds<usize>) {
+        let range = resolve(&range, Some(self.capacity()));
+        let words = self.words_mut();
+        for_each_word(range, |index, mask| words[index] &= !mask);
+    }
+
+    /// Flip every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range is unbounded above, or doesn't fit, see [`Bits::grow_to`].
+    fn toggle_range(&mut self, range: impl RangeBounds<usize>) {
+        let range = resolve(&range, None);
+        self.grow_to(range.end);
+        let words = self.words_mut();
+        for_each_word(range, |index, mask| words[index] ^= mask);
+    }
+
+    /// The number of bits set in `range`.
+    fn count_range(&self, range: impl RangeBounds<usize>) -> usize {
+        let range = resolve(&range, Some(self.capacity()));
+        let words = self.words();
+        let mut count = 0;
+        for_each_word(range, |index, mask| {
+            count += (words[index] & mask).count_ones() as usize;
+        });
+        count
+    }
+
+    /// The number of bits set.
+    fn count(&self) -> usize {
+        self.words()
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words().iter().all(|&word| word == 0)
+    }
+
+    /// Take out every bit, keeping the capacity.
+    fn clear(&mut self) {
+        self.words_mut().fill(0);
+    }
+
+    /// The bits that are set, in increasing order.
+    fn iter(&self) -> Ones<'_> {
+        Ones {
+            words: self.words(),
+            index: 0,
+            current: self.words().first().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Turn a range into a concrete `start..end`, clamping the end to `limit` if given.
+///
+/// # Panics
+///
+/// If there's no end and no limit to fall back on, or the range includes `usize::MAX`
+/// and there's no limit to clamp it to.
+fn resolve(range: &impl RangeBounds<usize>, limit: Option<usize>) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        // Excluding usize::MAX leaves nothing, which starting at it does just as well
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => match (end.checked_add(1), limit) {
+            (Some(end), _) => end,
+            (None, Some(limit)) => limit,
+            (None, None) => panic!("Bit {end} is past the end of any set"),
+        },
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => limit.expect("Can't add an unbounded range of bits"),
+    };
+    let end = limit.map_or(end, |limit| end.min(limit));
+    start..end.max(start)
+}
+
+/// Call `f` with each word index `range` touches, and a mask of its bits in that word.
+fn for_each_word(range: Range<usize>, mut f: impl FnMut(usize, u64)) {
+    if range.is_empty() {
+        return;
+    }
+
+    let first = range.start / WORD;
+    let last = (range.end - 1) / WORD;
+    for index in first..=last {
+        let low = if index == first {
+            range.start % WORD
+        } else {
+            0
+        };
+        let high = if index == last {
+            (range.end - 1) % WORD + 1
+        } else {
+            WORD
+        };
+        f(index, (u64::MAX >> (WORD - (high - low))) << low);
+    }
+}
+
+/// The set bits of a [`Bits`], from [`Bits::iter`].
+#[derive(Debug, Clone)]
+pub struct Ones<'a> {
+    words: &'a [u64],
+    index: usize,
+    /// What's left of `words[index]`
+    current: u64,
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.index * WORD + bit)
+    }
+}
+
+/// A set of bits below `WORDS * 64`, stored inline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitArray<const WORDS: usize> {
+    words: [u64; WORDS],
+}
+
+impl<const WORDS: usize> BitArray<WORDS> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { words: [0; WORDS] }
+    }
+}
+
+impl<const WORDS: usize> Default for BitArray<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WORDS: usize> Bits for BitArray<WORDS> {
+    fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.words
+    }
+
+    fn grow_to(&mut self, bits: usize) {
+        assert!(
+            bits <= WORDS * WORD,
+            "Bit {} is past the end of a {}-bit array",
+            bits - 1,
+            WORDS * WORD
+        );
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for BitArray<WORDS> {
+    fn from_iter<I: IntoIterator<Item = usize>>(bits: I) -> Self {
+        let mut set = Self::new();
+        for bit in bits {
+            set.insert(bit);
+        }
+        set
+    }
+}
+
+/// A set of bits on the heap, growing to fit whatever's added.
+#[derive(Debug, Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty set with room for bits `0..bits` before it needs to grow.
+    #[must_use]
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: vec![0; bits.div_ceil(WORD)],
+        }
+    }
+
+    /// The words up to the last one with anything in it.
+    fn significant(&self) -> &[u64] {
+        let len = self
+            .words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map_or(0, |i| i + 1);
+        &self.words[..len]
+    }
+}
+
+impl Bits for BitSet {
+    fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.words
+    }
+
+    fn grow_to(&mut self, bits: usize) {
+        let words = bits.div_ceil(WORD);
+        if words > self.words.len() {
+            self.words.resize(words, 0);
+        }
+    }
+}
+
+/// Sets are equal if they hold the same bits, however much room they have.
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant() == other.significant()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(bits: I) -> Self {
+        let mut set = Self::new();
+        set.extend(bits);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, bits: I) {
+        for bit in bits {
+            self.insert(bit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_single_bits() {
+        let mut set: BitArray<2> = BitArray::new();
+        assert!(set.is_empty());
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(64));
+        assert!(set.insert(127));
+        assert!(set.contains(3) && set.contains(64) && set.contains(127));
+        assert!(!set.contains(4) && !set.contains(500));
+        assert_eq!(set.count(), 3);
+
+        assert!(set.remove(64));
+        assert!(!set.remove(64));
+        assert!(!set.remove(500));
+        assert!(!set.toggle(3));
+        assert!(set.toggle(0));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 127]);
+
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.capacity(), 128);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bit 64 is past the end of a 64-bit array")]
+    fn test_array_overflow() {
+        let mut set: BitArray<1> = BitArray::new();
+        set.insert(64);
+    }
+
+    #[test]
+    fn test_bitset_grows() {
+        let mut set = BitSet::new();
+        assert_eq!(set.capacity(), 0);
+        set.insert(1000);
+        assert!(set.capacity() > 1000);
+        set.insert_range(2000..2010);
+        assert_eq!(set.count(), 11);
+        assert_eq!(set.iter().next(), Some(1000));
+        assert_eq!(set.iter().last(), Some(2009));
+
+        // Removing and counting past the end is fine, there's nothing there
+        set.remove_range(1005..);
+        assert_eq!(set.count_range(..), 1);
+        assert_eq!(set.count_range(5000..6000), 0);
+    }
+
+    #[test]
+    fn test_bitset_equality_ignores_capacity() {
+        let small: BitSet = [1, 5, 70].into_iter().collect();
+        let mut big = BitSet::with_capacity(10_000);
+        big.extend([70, 5, 1]);
+        assert_eq!(small, big);
+
+        big.insert(9999);
+        assert_ne!(small, big);
+        big.remove(9999);
+        assert_eq!(small, big);
+    }
+
+    #[test]
+    fn test_ranges_match_bit_by_bit() {
+        // Every range over a few words, against a plain Vec<bool>
+        const BITS: usize = 200;
+        let mut set = BitSet::with_capacity(BITS);
+        let mut reference = [false; BITS];
+
+        for start in 0..BITS {
+            for end in (start..=BITS).step_by(7) {
+                match (start + end) % 3 {
+                    0 => {
+                        set.insert_range(start..end);
+                        reference[start..end].fill(true);
+                    }
+                    1 => {
+                        set.remove_range(start..end);
+                        reference[start..end].fill(false);
+                    }
+                    _ => {
+                        set.toggle_range(start..end);
+                        for bit in &mut reference[start..end] {
+                            *bit = !*bit;
+                        }
+                    }
+                }
+                let expected = reference[start..end].iter().filter(|&&b| b).count();
+                assert_eq!(set.count_range(start..end), expected, "{start}..{end}");
+            }
+        }
+
+        let expected: Vec<usize> = (0..BITS).filter(|&bit| reference[bit]).collect();
+        assert_eq!(set.iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let mut set: BitArray<4> = BitArray::new();
+        set.insert_range(10..=20);
+        assert_eq!(set.count(), 11);
+        set.toggle_range(..64);
+        assert_eq!(set.count(), 64 - 11);
+        set.remove_range(..);
+        assert!(set.is_empty());
+        set.insert_range(5..5);
+        assert!(set.is_empty());
+
+        // Ranges up to the very last usize are fine wherever they get clamped
+        set.insert_range(..256);
+        assert_eq!(set.count_range(250..=usize::MAX), 6);
+        set.remove_range(200..=usize::MAX);
+        assert_eq!(set.count(), 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end of any set")]
+    fn test_range_past_usize_max() {
+        let mut set = BitSet::new();
+        set.insert_range(usize::MAX - 1..=usize::MAX);
+    }
+}
@@ -0,0 +1,339 @@
+//! Permutations, combinations and subsets without allocating for each one.
+//!
+//! Permutations are generated in place with Heap's algorithm and handed to a callback
+//! as a slice, combinations come out as a slice of indices, and subsets of up to 63
+//! items are bitmasks.
+
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::ops::Range;
+
+/// Call `f` with every permutation of `items`, rearranging them in place.
+///
+/// `items` is left in whatever order the last permutation had.
+pub fn for_each_permutation<T>(items: &mut [T], mut f: impl FnMut(&[T])) {
+    let Ok(()) = try_for_each_permutation(items, |p| {
+        f(p);
+        Ok::<(), Infallible>(())
+    });
+}
+
+/// Like [`for_each_permutation`], but stopping at the first error from `f`.
+///
+/// # Errors
+///
+/// The first error `f` returns.
+pub fn try_for_each_permutation<T, E>(
+    items: &mut [T],
+    f: impl FnMut(&[T]) -> Result<(), E>,
+) -> Result<(), E> {
+    let all = 0..items.len();
+    let mut counters = vec![0; all.len()];
+    permute(items, all, &mut counters, f)
+}
+
+/// Heap's algorithm over `items[range]`, with `f` seeing the whole slice each time.
+///
+/// `counters` is scratch space for at least one counter per item in `range`, so callers
+/// permuting many times over can allocate it once.
+fn permute<T, E>(
+    items: &mut [T],
+    range: Range<usize>,
+    counters: &mut [usize],
+    mut f: impl FnMut(&[T]) -> Result<(), E>,
+) -> Result<(), E> {
+    let (fixed, n) = (range.start, range.len());
+    counters[..n].fill(0);
+
+    f(items)?;
+
+    let mut i = 1;
+    while i < n {
+        if counters[i] < i {
+            let swap_with = if i % 2 == 0 { 0 } else { counters[i] };
+            items.swap(fixed + swap_with, fixed + i);
+            f(items)?;
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Call `f` with every route visiting each of `items` once, skipping reversed duplicates.
+///
+/// When walking a route costs the same in both directions, a route and its reverse are
+/// the same answer, so only the one starting at the smaller end is generated: each pair
+/// of ends with the smaller first, then every order of the items between them, for
+/// `n! / 2` routes rather than `n!`. The items must all be distinct.
+///
+/// # Errors
+///
+/// The first error `f` returns.
+pub fn try_for_each_path<T: PartialOrd, E>(
+    items: &mut [T],
+    mut f: impl FnMut(&[T]) -> Result<(), E>,
+) -> Result<(), E> {
+    let n = items.len();
+    if n < 2 {
+        return f(items);
+    }
+
+    // Heap's algorithm leaves the middle shuffled, so sorting puts everything back in a
+    // known order before picking the next pair of ends
+    let mut counters = vec![0; n - 2];
+    for first in 0..n {
+        sort(items);
+        items.swap(0, first);
+        for last in 1..n {
+            sort(&mut items[1..]);
+            if items[0] < items[last] {
+                items.swap(last, n - 1);
+                permute(items, 1..n - 1, &mut counters, &mut f)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sort<T: PartialOrd>(items: &mut [T]) {
+    items.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Call `f` with every round trip through `items`, skipping rotations and reflections.
+///
+/// The first item is fixed as the start, since rotating a cycle doesn't change it, so
+/// `(n - 1)!` orders are generated rather than `n!`. Of each cycle and its mirror image,
+/// only the one whose second item is smaller than its last is passed to `f`, so it sees
+/// `(n - 1)! / 2` of them. The items must all be distinct.
+///
+/// # Errors
+///
+/// The first error `f` returns.
+pub fn try_for_each_cycle<T: PartialOrd, E>(
+    items: &mut [T],
+    mut f: impl FnMut(&[T]) -> Result<(), E>,
+) -> Result<(), E> {
+    let rest = 1..items.len().max(1);
+    let mut counters = vec![0; rest.len()];
+    permute(items, rest, &mut counters, |cycle| match cycle {
+        [_, second, .., last] if second > last => Ok(()),
+        _ => f(cycle),
+    })
+}
+
+/// Call `f` with the indices of every way to choose `k` of `n` items, in lexicographic order.
+pub fn for_each_combination(n: usize, k: usize, mut f: impl FnMut(&[usize])) {
+    if k > n {
+        return;
+    }
+
+    let mut indices: Vec<usize> = (0..k).collect();
+    loop {
+        f(&indices);
+
+        // Bump the rightmost index that still has room, and reset everything after it
+        let Some(i) = (0..k).rev().find(|&i| indices[i] < i + n - k) else {
+            return;
+        };
+        indices[i] += 1;
+        for j in i + 1..k {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+/// Every subset of `n` items as a bitmask, bit `i` set if item `i` is in it.
+///
+/// # Panics
+///
+/// If `n` is 64 or more, too many to fit in a mask.
+pub fn subsets(n: u32) -> impl Iterator<Item = u64> {
+    assert!(n < 64, "Can't enumerate subsets of {n} items");
+    0..1 << n
+}
+
+/// Every subset of exactly `k` of `n` items as a bitmask, in increasing order.
+///
+/// # Panics
+///
+/// If `n` is 64 or more, too many to fit in a mask.
+pub fn subsets_of_size(n: u32, k: u32) -> impl Iterator<Item = u64> {
+    assert!(n < 64, "Can't enumerate subsets of {n} items");
+    let limit = 1 << n;
+    let first = (k <= n).then(|| (1 << k) - 1);
+
+    std::iter::successors(first, move |&mask: &u64| {
+        if mask == 0 {
+            return None;
+        }
+        // Gosper's hack: the next larger number with the same number of bits set
+        let lowest = mask & mask.wrapping_neg();
+        let ripple = mask + lowest;
+        let next = (((ripple ^ mask) >> 2) / lowest) | ripple;
+        (next < limit).then_some(next)
+    })
+}
+
+/// The indices of the items in a subset bitmask, in increasing order.
+pub fn members(mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::successors((mask != 0).then_some(mask), |&rest| {
+        let rest = rest & (rest - 1);
+        (rest != 0).then_some(rest)
+    })
+    .map(|rest| rest.trailing_zeros() as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn factorial(n: usize) -> usize {
+        (1..=n).product()
+    }
+
+    #[test]
+    fn test_permutations() {
+        for n in 0..=6 {
+            let mut items: Vec<usize> = (0..n).collect();
+            let mut seen = HashSet::new();
+            for_each_permutation(&mut items, |p| {
+                assert!(seen.insert(p.to_vec()), "Repeated permutation {p:?}");
+            });
+            assert_eq!(seen.len(), factorial(n));
+        }
+    }
+
+    #[test]
+    fn test_try_for_each_permutation_stops() {
+        let mut items = [1, 2, 3, 4];
+        let mut calls = 0;
+        let result = try_for_each_permutation(&mut items, |_| {
+            calls += 1;
+            if calls == 5 {
+                Err("stop")
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err("stop"));
+        assert_eq!(calls, 5);
+    }
+
+    #[test]
+    fn test_paths_skip_reversals() {
+        for n in 2..=6 {
+            let mut items: Vec<usize> = (0..n).collect();
+            let mut seen = HashSet::new();
+            let Ok(()) = try_for_each_path(&mut items, |p| {
+                let mut reversed = p.to_vec();
+                reversed.reverse();
+                assert!(!seen.contains(&reversed), "Saw {p:?} both ways round");
+                assert!(seen.insert(p.to_vec()), "Repeated path {p:?}");
+                assert!(p[0] < p[p.len() - 1]);
+                Ok::<(), Infallible>(())
+            });
+            assert_eq!(seen.len(), factorial(n) / 2);
+        }
+
+        // With fewer than two items the only route is the items as they are
+        for n in 0..2 {
+            let mut items: Vec<usize> = (0..n).collect();
+            let mut calls = 0;
+            let Ok(()) = try_for_each_path(&mut items, |_| {
+                calls += 1;
+                Ok::<(), Infallible>(())
+            });
+            assert_eq!(calls, 1);
+        }
+    }
+
+    #[test]
+    fn test_cycles_skip_rotations_and_reflections() {
+        // Canonical form of a cycle: rotated to start at 0, in the smaller direction
+        fn canonical(cycle: &[usize]) -> Vec<usize> {
+            let start = cycle.iter().position(|&x| x == 0).unwrap();
+            let mut rotated: Vec<usize> = cycle[start..]
+                .iter()
+                .chain(&cycle[..start])
+                .copied()
+                .collect();
+            if rotated[1] > rotated[rotated.len() - 1] {
+                rotated[1..].reverse();
+            }
+            rotated
+        }
+
+        for n in 3..=7 {
+            let mut items: Vec<usize> = (0..n).collect();
+            let mut seen = HashSet::new();
+            let Ok(()) = try_for_each_cycle(&mut items, |c| {
+                assert_eq!(c[0], 0);
+                assert!(seen.insert(canonical(c)), "Repeated cycle {c:?}");
+                Ok::<(), Infallible>(())
+            });
+            assert_eq!(seen.len(), factorial(n - 1) / 2);
+        }
+    }
+
+    #[test]
+    fn test_combinations() {
+        let mut all = Vec::new();
+        for_each_combination(4, 2, |c| all.push(c.to_vec()));
+        assert_eq!(
+            all,
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+
+        let mut count = 0;
+        for_each_combination(10, 4, |_| count += 1);
+        assert_eq!(count, 210);
+
+        let mut count = 0;
+        for_each_combination(3, 0, |c| {
+            assert!(c.is_empty());
+            count += 1;
+        });
+        assert_eq!(count, 1);
+
+        for_each_combination(2, 3, |_| panic!("Can't choose 3 of 2"));
+    }
+
+    #[test]
+    fn test_subsets() {
+        assert_eq!(subsets(3).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+        assert_eq!(subsets(0).count(), 1);
+    }
+
+    #[test]
+    fn test_subsets_of_size() {
+        assert_eq!(
+            subsets_of_size(4, 2).collect::<Vec<_>>(),
+            vec![0b0011, 0b0101, 0b0110, 0b1001, 0b1010, 0b1100]
+        );
+        assert_eq!(subsets_of_size(20, 5).count(), 15504);
+        assert!(subsets_of_size(20, 5).all(|mask| mask.count_ones() == 5));
+        assert_eq!(subsets_of_size(5, 0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(subsets_of_size(3, 3).collect::<Vec<_>>(), vec![0b111]);
+        assert_eq!(subsets_of_size(3, 4).count(), 0);
+    }
+
+    #[test]
+    fn test_members() {
+        assert_eq!(members(0b1011_0010).collect::<Vec<_>>(), vec![1, 4, 5, 7]);
+        assert_eq!(members(0).count(), 0);
+        assert_eq!(members(1 << 63).collect::<Vec<_>>(), vec![63]);
+    }
+}
@@ -0,0 +1,497 @@
+//! Santa and his helpers delivering presents around an infinite grid of houses.
+//!
+//! Any number of agents take turns following one list of directions, by a [`Schedule`].
+//! Each delivers to the house they start at, then to every house they move to. [`deliver`]
+//! counts the presents each agent leaves at each house, [`routes`] keeps the order they
+//! went in, and [`timeline`] records every visit with when it happened and who made it.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::geometry::{Direction, Point, Rect};
+
+/// Where Santa starts, and everyone else unless told otherwise.
+pub const ORIGIN: Point<i32> = Point::new(0, 0);
+
+/// Who takes each move, over and over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// `round-robin`: one move each in turn
+    RoundRobin,
+    /// `weighted=3,1`: this many moves in a row each, in turn
+    Weighted(Vec<usize>),
+    /// `pattern=0010`: agents by number, `0-9` then `a-z`
+    Pattern(Vec<usize>),
+}
+
+impl Schedule {
+    /// Which agent makes each move in one cycle of the schedule.
+    ///
+    /// # Errors
+    ///
+    /// If the schedule doesn't fit that many agents, or never gives anyone a move.
+    pub fn turns(&self, agents: usize) -> Result<Vec<usize>> {
+        let turns = match self {
+            Schedule::RoundRobin => (0..agents).collect(),
+            Schedule::Weighted(weights) => {
+                if weights.len() != agents {
+                    bail!(
+                        "{} weights given for {agents} agents, there should be one each",
+                        weights.len()
+                    );
+                }
+                weights
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(agent, &weight)| std::iter::repeat_n(agent, weight))
+                    .collect()
+            }
+            Schedule::Pattern(pattern) => {
+                if let Some(agent) = pattern.iter().find(|&&agent| agent >= agents) {
+                    bail!("The pattern has agent {agent}, but there are only {agents} agents");
+                }
+                pattern.clone()
+            }
+        };
+        if turns.is_empty() {
+            bail!("The schedule never gives anyone a move");
+        }
+        Ok(turns)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "round-robin" {
+            return Ok(Schedule::RoundRobin);
+        }
+        if let Some(weights) = s.strip_prefix("weighted=") {
+            let weights = weights
+                .split(',')
+                .map(|weight| {
+                    weight
+                        .trim()
+                        .parse()
+                        .map_err(|e| anyhow!("Invalid weight {weight:?}: {e}"))
+                })
+                .collect::<Result<_>>()?;
+            return Ok(Schedule::Weighted(weights));
+        }
+        if let Some(pattern) = s.strip_prefix("pattern=") {
+            let pattern = pattern
+                .chars()
+                .map(|c| {
+                    // to_digit would take upper case too, which would be two names for an agent
+                    Some(c)
+                        .filter(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+                        .and_then(|c| c.to_digit(36))
+                        .map(|agent| agent as usize)
+                        .ok_or_else(|| anyhow!("Invalid agent {c:?} in pattern, use 0-9 or a-z"))
+                })
+                .collect::<Result<_>>()?;
+            return Ok(Schedule::Pattern(pattern));
+        }
+        bail!("Unknown schedule {s:?}, expected round-robin, weighted=N,N... or pattern=AGENTS")
+    }
+}
+
+/// Follow the directions in `raw` from `starts`, taking turns by `schedule`, and call
+/// `visit` with the step, agent and house of every delivery: each agent's starting house
+/// at step 0, then one for each move.
+fn walk(
+    raw: &str,
+    starts: &[Point<i32>],
+    schedule: &Schedule,
+    mut visit: impl FnMut(usize, usize, Point<i32>),
+) -> Result<()> {
+    let turns = schedule.turns(starts.len())?;
+    let mut positions = starts.to_vec();
+    for (agent, &start) in starts.iter().enumerate() {
+        visit(0, agent, start);
+    }
+
+    for (step, (char, &agent)) in (1..).zip(raw.trim().chars().zip(turns.iter().cycle())) {
+        let pos = &mut positions[agent];
+        *pos = pos.step(Direction::try_from(char)?);
+        visit(step, agent, *pos);
+    }
+    Ok(())
+}
+
+/// Every house each agent goes to, in order, starting from their starting house.
+///
+/// # Errors
+///
+/// If the schedule doesn't fit the agents, or there's an invalid direction.
+pub fn routes(
+    raw: &str,
+    starts: &[Point<i32>],
+    schedule: &Schedule,
+) -> Result<Vec<Vec<Point<i32>>>> {
+    let mut routes = vec![Vec::new(); starts.len()];
+    walk(raw, starts, schedule, |_, agent, house| {
+        routes[agent].push(house);
+    })?;
+    Ok(routes)
+}
+
+/// How many presents each agent delivered to each house.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deliveries {
+    agents: Vec<HashMap<Point<i32>, usize>>,
+}
+
+impl Deliveries {
+    /// How many agents there are.
+    #[must_use]
+    pub fn agents(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// How many houses `agent` delivered to.
+    #[must_use]
+    pub fn houses(&self, agent: usize) -> usize {
+        self.agents[agent].len()
+    }
+
+    /// How many houses got at least one present from anyone.
+    #[must_use]
+    pub fn distinct(&self) -> usize {
+        self.agents
+            .iter()
+            .flat_map(HashMap::keys)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// How many houses got presents from both `a` and `b`.
+    #[must_use]
+    pub fn overlap(&self, a: usize, b: usize) -> usize {
+        self.agents[a]
+            .keys()
+            .filter(|house| self.agents[b].contains_key(house))
+            .count()
+    }
+
+    /// How many houses got presents from more than one agent.
+    #[must_use]
+    pub fn shared(&self) -> usize {
+        let mut seen = HashSet::new();
+        let mut shared = HashSet::new();
+        for house in self.agents.iter().flat_map(HashMap::keys) {
+            if !seen.insert(house) {
+                shared.insert(house);
+            }
+        }
+        shared.len()
+    }
+
+    /// How many presents each house got from everyone together.
+    #[must_use]
+    pub fn presents(&self) -> HashMap<Point<i32>, usize> {
+        let mut presents = HashMap::new();
+        for (&house, &count) in self.agents.iter().flatten() {
+            *presents.entry(house).or_default() += count;
+        }
+        presents
+    }
+
+    /// The `n` houses with the most presents, most first, then top to bottom and left to
+    /// right so ties come out the same every time.
+    #[must_use]
+    pub fn most_visited(&self, n: usize) -> Vec<(Point<i32>, usize)> {
+        let mut houses: Vec<(Point<i32>, usize)> = self.presents().into_iter().collect();
+        houses.sort_unstable_by_key(|&(house, count)| (Reverse(count), house.y, house.x));
+        houses.truncate(n);
+        houses
+    }
+
+    /// How many houses got each number of presents.
+    #[must_use]
+    pub fn histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for count in self.presents().into_values() {
+            *histogram.entry(count).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// The smallest rectangle holding every house that got a present, or `None` if there
+    /// weren't any agents to deliver them.
+    #[must_use]
+    pub fn bounds(&self) -> Option<Rect<i32>> {
+        let mut houses = self.agents.iter().flat_map(HashMap::keys);
+        let first = *houses.next()?;
+        Some(houses.fold(Rect::new(first, first), |bounds, &house| {
+            Rect::new(
+                Point::new(bounds.min.x.min(house.x), bounds.min.y.min(house.y)),
+                Point::new(bounds.max.x.max(house.x), bounds.max.y.max(house.y)),
+            )
+        }))
+    }
+}
+
+/// Follow the directions in `raw` with an agent at each of `starts`, taking turns by
+/// `schedule`, and count the presents.
+///
+/// # Errors
+///
+/// If the schedule doesn't fit the agents, or there's an invalid direction.
+pub fn deliver(raw: &str, starts: &[Point<i32>], schedule: &Schedule) -> Result<Deliveries> {
+    let mut agents = vec![HashMap::new(); starts.len()];
+    walk(raw, starts, schedule, |_, agent, house| {
+        *agents[agent].entry(house).or_default() += 1;
+    })?;
+    Ok(Deliveries { agents })
+}
+
+/// One delivery to a house: the step it happened at, 0 for the starting houses and then
+/// one per move, and which agent made it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visit {
+    pub step: usize,
+    pub agent: usize,
+}
+
+/// Every visit to every house, in the order they happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    houses: HashMap<Point<i32>, Vec<Visit>>,
+    /// How many distinct houses had been visited after each step
+    distinct: Vec<usize>,
+}
+
+impl Timeline {
+    /// How many moves there were.
+    #[must_use]
+    pub fn moves(&self) -> usize {
+        self.distinct.len().saturating_sub(1)
+    }
+
+    /// Every visit to `house`, earliest first.
+    #[must_use]
+    pub fn visits(&self, house: Point<i32>) -> &[Visit] {
+        self.houses.get(&house).map_or(&[], Vec::as_slice)
+    }
+
+    /// When `house` was first visited and by who, if it ever was. If several agents start
+    /// there, it's the lowest numbered.
+    #[must_use]
+    pub fn first_visit(&self, house: Point<i32>) -> Option<Visit> {
+        self.visits(house).first().copied()
+    }
+
+    /// The houses that only got the one present, top to bottom and left to right.
+    #[must_use]
+    pub fn visited_once(&self) -> Vec<Point<i32>> {
+        let mut once: Vec<Point<i32>> = self
+            .houses
+            .iter()
+            .filter(|(_, visits)| visits.len() == 1)
+            .map(|(&house, _)| house)
+            .collect();
+        once.sort_unstable_by_key(|house| (house.y, house.x));
+        once
+    }
+
+    /// How many distinct houses had been visited after `step`.
+    #[must_use]
+    pub fn distinct_after(&self, step: usize) -> usize {
+        self.distinct[step.min(self.moves())]
+    }
+
+    /// The fewest moves it took for `n` distinct houses to have been visited, or `None` if
+    /// there never were that many.
+    #[must_use]
+    pub fn moves_to_reach(&self, n: usize) -> Option<usize> {
+        // Distinct counts only ever go up, so the first step to reach `n` can be searched for
+        let step = self.distinct.partition_point(|&distinct| distinct < n);
+        (step < self.distinct.len()).then_some(step)
+    }
+}
+
+/// Follow the directions in `raw` with an agent at each of `starts`, taking turns by
+/// `schedule`, and record every visit.
+///
+/// # Errors
+///
+/// If the schedule doesn't fit the agents, or there's an invalid direction.
+pub fn timeline(raw: &str, starts: &[Point<i32>], schedule: &Schedule) -> Result<Timeline> {
+    let mut houses: HashMap<Point<i32>, Vec<Visit>> = HashMap::new();
+    let mut distinct = vec![0];
+    walk(raw, starts, schedule, |step, agent, house| {
+        houses.entry(house).or_default().push(Visit { step, agent });
+        // The starting houses all share step 0
+        if step == distinct.len() {
+            distinct.push(0);
+        }
+        distinct[step] = houses.len();
+    })?;
+    Ok(Timeline { houses, distinct })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schedules() -> Result<()> {
+        assert_eq!(Schedule::RoundRobin.turns(3)?, [0, 1, 2]);
+        assert_eq!("weighted=2,1".parse::<Schedule>()?.turns(2)?, [0, 0, 1]);
+        assert_eq!("pattern=0a1".parse::<Schedule>()?.turns(11)?, [0, 10, 1]);
+
+        let err = |schedule: &str, agents| {
+            schedule
+                .parse::<Schedule>()
+                .and_then(|schedule| schedule.turns(agents))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("weighted=1,1,1", 2),
+            "3 weights given for 2 agents, there should be one each"
+        );
+        assert_eq!(
+            err("weighted=0,0", 2),
+            "The schedule never gives anyone a move"
+        );
+        assert_eq!(
+            err("pattern=012", 2),
+            "The pattern has agent 2, but there are only 2 agents"
+        );
+        assert!(err("pattern=0-1", 2).starts_with("Invalid agent '-'"));
+        assert!(err("pattern=0A", 11).starts_with("Invalid agent 'A'"));
+        assert!(err("weighted=1,x", 2).starts_with("Invalid weight \"x\""));
+        assert!(err("random", 2).starts_with("Unknown schedule"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deliver() -> Result<()> {
+        // The two puzzle parts are one and two agents taking turns
+        let alone = deliver("^>v<", &[ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(alone.distinct(), 4);
+        assert_eq!(alone.shared(), 0);
+
+        let pair = deliver("^v^v^v^v^v", &[ORIGIN, ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(pair.distinct(), 11);
+        assert_eq!(pair.houses(0), 6);
+        assert_eq!(pair.overlap(0, 1), 1);
+
+        // Santa makes two moves for every one of Robo-Santa's: ^^ v >> <
+        let weighted = deliver("^^v>><", &[ORIGIN, ORIGIN], &Schedule::Weighted(vec![2, 1]))?;
+        assert_eq!(weighted.houses(0), 5);
+        assert_eq!(weighted.houses(1), 3);
+        assert_eq!(weighted.overlap(0, 1), 1);
+        assert_eq!(weighted.distinct(), 7);
+
+        // Three agents, apart from each other, with the last never moving
+        let starts = [ORIGIN, Point::new(1, 0), Point::new(5, 5)];
+        let apart = deliver(">><", &starts, &Schedule::Pattern(vec![0, 1]))?;
+        assert_eq!(apart.agents(), 3);
+        assert_eq!(apart.houses(0), 2);
+        assert_eq!(apart.houses(1), 2);
+        assert_eq!(apart.houses(2), 1);
+        assert_eq!(apart.overlap(0, 1), 1);
+        assert_eq!(apart.overlap(0, 2), 0);
+        assert_eq!(apart.shared(), 1);
+        assert_eq!(apart.distinct(), 4);
+
+        assert!(deliver("^x", &[ORIGIN], &Schedule::RoundRobin).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_presents() -> Result<()> {
+        // Back and forth over two houses, with the start getting the extra one
+        let deliveries = deliver("^v^v>", &[ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(
+            deliveries.most_visited(2),
+            [(ORIGIN, 3), (Point::new(0, -1), 2)]
+        );
+        assert_eq!(
+            deliveries.histogram(),
+            BTreeMap::from([(1, 1), (2, 1), (3, 1)])
+        );
+        assert_eq!(
+            deliveries.bounds(),
+            Some(Rect::new(Point::new(0, -1), Point::new(1, 0)))
+        );
+        assert_eq!(Deliveries { agents: vec![] }.bounds(), None);
+
+        // Both agents' presents count towards a house
+        let pair = deliver("^^", &[ORIGIN, ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(pair.presents()[&Point::new(0, -1)], 2);
+        assert_eq!(pair.presents()[&ORIGIN], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_routes() -> Result<()> {
+        let routes = routes("^>v<", &[ORIGIN, Point::new(5, 5)], &Schedule::RoundRobin)?;
+        assert_eq!(
+            routes,
+            [
+                vec![ORIGIN, Point::new(0, -1), Point::new(0, 0)],
+                vec![Point::new(5, 5), Point::new(6, 5), Point::new(5, 5)],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeline() -> Result<()> {
+        // Santa goes up and comes back, Robo-Santa goes right twice
+        let timeline = timeline("^>v>", &[ORIGIN, ORIGIN], &Schedule::RoundRobin)?;
+        assert_eq!(timeline.moves(), 4);
+        assert_eq!(
+            timeline.visits(ORIGIN),
+            [
+                Visit { step: 0, agent: 0 },
+                Visit { step: 0, agent: 1 },
+                Visit { step: 3, agent: 0 },
+            ]
+        );
+        assert_eq!(
+            timeline.first_visit(Point::new(2, 0)),
+            Some(Visit { step: 4, agent: 1 })
+        );
+        assert_eq!(timeline.first_visit(Point::new(9, 9)), None);
+        assert_eq!(timeline.visits(Point::new(9, 9)), []);
+        assert_eq!(
+            timeline.visited_once(),
+            [Point::new(0, -1), Point::new(1, 0), Point::new(2, 0)]
+        );
+
+        assert_eq!(timeline.distinct_after(0), 1);
+        assert_eq!(timeline.distinct_after(3), 3);
+        assert_eq!(timeline.distinct_after(100), 4);
+        assert_eq!(timeline.moves_to_reach(0), Some(0));
+        assert_eq!(timeline.moves_to_reach(1), Some(0));
+        assert_eq!(timeline.moves_to_reach(3), Some(2));
+        assert_eq!(timeline.moves_to_reach(4), Some(4));
+        assert_eq!(timeline.moves_to_reach(5), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeline_matches_deliveries() -> Result<()> {
+        let raw = "^^>v<<v>>^^^v<<>>vv";
+        let schedule = Schedule::Weighted(vec![3, 1, 2]);
+        let starts = [ORIGIN, Point::new(2, 2), ORIGIN];
+        let timeline = timeline(raw, &starts, &schedule)?;
+        let deliveries = deliver(raw, &starts, &schedule)?;
+
+        assert_eq!(timeline.moves(), raw.len());
+        assert_eq!(timeline.distinct_after(raw.len()), deliveries.distinct());
+        for (house, count) in deliveries.presents() {
+            assert_eq!(timeline.visits(house).len(), count, "{house}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,853 @@
+//! Santa's floor from a stream of brackets, a chunk at a time in constant memory.
+//!
+//! Each chunk is counted by the fastest kernel available: SSE2 on `x86_64`, where it's
+//! always present, and eight bytes at a time in a `u64` everywhere else. The kernels all
+//! check every byte, so an invalid one is reported at its offset from the start of the
+//! whole stream, not just its chunk.
+//!
+//! For more than the final floor, [`trace`] follows the route a bracket at a time and keeps
+//! a [`Trace`] to ask about it afterwards. Going the other way, [`shortest`] and [`random`]
+//! build instructions that end on a given floor and first enter the basement at a given
+//! position, for test fixtures.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+use std::ops::ControlFlow;
+
+use crate::rng::XorShift;
+
+/// How much of the stream is held in memory at once.
+pub const CHUNK: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum FloorError {
+    /// Something other than `(` or `)`, at this many bytes into the stream
+    Invalid {
+        byte: u8,
+        offset: u64,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for FloorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FloorError::Invalid { byte, offset } => write!(
+                f,
+                "Invalid character '{}' at offset {offset}",
+                byte.escape_ascii()
+            ),
+            FloorError::Io(e) => write!(f, "Failed to read instructions: {e}"),
+        }
+    }
+}
+
+impl Error for FloorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FloorError::Invalid { .. } => None,
+            FloorError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for FloorError {
+    fn from(e: io::Error) -> Self {
+        FloorError::Io(e)
+    }
+}
+
+/// Feed `reader` to `f` a chunk at a time, along with how far into the stream each chunk
+/// starts, until it runs out or `f` breaks with an answer.
+fn chunks<T>(
+    mut reader: impl Read,
+    mut f: impl FnMut(&[u8], u64) -> Result<ControlFlow<T>, FloorError>,
+) -> Result<Option<T>, FloorError> {
+    let mut buffer = vec![0; CHUNK];
+    let mut offset: u64 = 0;
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(None),
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if let ControlFlow::Break(answer) = f(&buffer[..read], offset)? {
+            return Ok(Some(answer));
+        }
+        offset += read as u64;
+    }
+}
+
+/// The floor Santa ends up on after following every bracket in `reader`.
+///
+/// # Errors
+///
+/// If reading fails, or there's anything but brackets, newlines included.
+pub fn floor(reader: impl Read) -> Result<i64, FloorError> {
+    let mut floor = 0;
+    chunks(reader, |chunk, offset| {
+        floor += count(chunk).map_err(|index| FloorError::Invalid {
+            byte: chunk[index],
+            offset: offset + index as u64,
+        })?;
+        Ok(ControlFlow::<()>::Continue(()))
+    })?;
+    Ok(floor)
+}
+
+/// The 1-based position of the bracket that first takes Santa below the ground floor, if any.
+///
+/// This has to follow the floor a bracket at a time, but still reads in chunks and stops as
+/// soon as it's found.
+///
+/// # Errors
+///
+/// If reading fails, or there's anything but brackets before the basement is reached.
+pub fn basement(reader: impl Read) -> Result<Option<u64>, FloorError> {
+    let mut floor: i64 = 0;
+    chunks(reader, |chunk, offset| {
+        for (index, &byte) in chunk.iter().enumerate() {
+            let offset = offset + index as u64;
+            floor += step(byte, offset)?;
+            if floor < 0 {
+                return Ok(ControlFlow::Break(offset + 1));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    })
+}
+
+/// Which way the bracket at `offset` sends Santa.
+fn step(byte: u8, offset: u64) -> Result<i64, FloorError> {
+    match byte {
+        b'(' => Ok(1),
+        b')' => Ok(-1),
+        _ => Err(FloorError::Invalid { byte, offset }),
+    }
+}
+
+/// A stretch of instructions, by their 1-based positions, both ends included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Run {
+    /// How many instructions it covers.
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+/// What's known about one floor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Visits {
+    /// The position Santa first got here, 0 for the ground floor he starts on
+    first: u64,
+    /// How many instructions left him here
+    time: u64,
+}
+
+/// Everything about Santa's route through the building, from a single pass over the
+/// instructions.
+///
+/// Positions are 1-based like part 2's, with 0 meaning before any instructions. Floors are
+/// always reached one after another, so each is kept in a `Vec` growing up from the ground
+/// floor or down from the first basement level, which only grows with the height of the
+/// building Santa covers. The exception is [`Trace::basement_entries`], one per trip down
+/// from the ground floor, so instructions that keep stepping in and out of the basement
+/// grow it with their length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// Floors 0, 1, 2...
+    up: Vec<Visits>,
+    /// Floors -1, -2, -3...
+    down: Vec<Visits>,
+    basement_entries: Vec<u64>,
+    above_ground: Option<Run>,
+    /// Where the run above ground Santa's on right now started
+    climbing: Option<u64>,
+    highest: i64,
+    lowest: i64,
+    floor: i64,
+    instructions: u64,
+}
+
+impl Trace {
+    fn new() -> Trace {
+        Trace {
+            up: vec![Visits { first: 0, time: 0 }],
+            down: Vec::new(),
+            basement_entries: Vec::new(),
+            above_ground: None,
+            climbing: None,
+            highest: 0,
+            lowest: 0,
+            floor: 0,
+            instructions: 0,
+        }
+    }
+
+    /// Where `floor` lives in `up` or `down`.
+    fn slot(floor: i64) -> (bool, usize) {
+        let index = if floor >= 0 { floor } else { -floor - 1 };
+        (
+            floor >= 0,
+            usize::try_from(index).expect("Floor is too far from the ground to index"),
+        )
+    }
+
+    fn visits(&self, floor: i64) -> Option<&Visits> {
+        match Trace::slot(floor) {
+            (true, index) => self.up.get(index),
+            (false, index) => self.down.get(index),
+        }
+    }
+
+    fn follow(&mut self, byte: u8, offset: u64) -> Result<(), FloorError> {
+        self.floor += step(byte, offset)?;
+        let position = offset + 1;
+        self.instructions = position;
+
+        let (up, index) = Trace::slot(self.floor);
+        let floors = if up { &mut self.up } else { &mut self.down };
+        if index == floors.len() {
+            floors.push(Visits {
+                first: position,
+                time: 0,
+            });
+            self.highest = self.highest.max(self.floor);
+            self.lowest = self.lowest.min(self.floor);
+        }
+        floors[index].time += 1;
+
+        if self.floor == -1 && byte == b')' {
+            self.basement_entries.push(position);
+        }
+        self.extend_above_ground(position);
+        Ok(())
+    }
+
+    /// Keep track of the longest run above ground, preferring the earliest of any ties.
+    fn extend_above_ground(&mut self, position: u64) {
+        if self.floor <= 0 {
+            self.climbing = None;
+            return;
+        }
+        let run = Run {
+            start: *self.climbing.get_or_insert(position),
+            end: position,
+        };
+        if self
+            .above_ground
+            .is_none_or(|longest| run.length() > longest.length())
+        {
+            self.above_ground = Some(run);
+        }
+    }
+
+    /// The floor Santa ends up on.
+    #[must_use]
+    pub fn floor(&self) -> i64 {
+        self.floor
+    }
+
+    /// How many instructions there were.
+    #[must_use]
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// The position Santa first got to `floor`, if he ever did.
+    #[must_use]
+    pub fn first_reaching(&self, floor: i64) -> Option<u64> {
+        self.visits(floor).map(|visits| visits.first)
+    }
+
+    /// Every position that took Santa from the ground floor into the basement.
+    #[must_use]
+    pub fn basement_entries(&self) -> &[u64] {
+        &self.basement_entries
+    }
+
+    /// The highest floor Santa got to, and the position he first got there.
+    #[must_use]
+    pub fn highest(&self) -> (i64, u64) {
+        (self.highest, self.up[self.up.len() - 1].first)
+    }
+
+    /// The lowest floor Santa got to, and the position he first got there.
+    #[must_use]
+    pub fn lowest(&self) -> (i64, u64) {
+        (
+            self.lowest,
+            self.down.last().map_or(0, |visits| visits.first),
+        )
+    }
+
+    /// How many instructions left Santa on each floor, from the lowest up. Every floor in
+    /// between is included, even those he only passed through.
+    pub fn histogram(&self) -> impl Iterator<Item = (i64, u64)> + '_ {
+        self.down
+            .iter()
+            .rev()
+            .chain(&self.up)
+            .zip(self.lowest..)
+            .map(|(visits, floor)| (floor, visits.time))
+    }
+
+    /// The longest stretch of instructions that kept Santa above the ground floor, the
+    /// earliest if there's a tie.
+    #[must_use]
+    pub fn longest_above_ground(&self) -> Option<Run> {
+        self.above_ground
+    }
+}
+
+/// Follow every instruction in `reader` and keep a [`Trace`] of the route.
+///
+/// # Errors
+///
+/// If reading fails, or there's anything but brackets.
+pub fn trace(reader: impl Read) -> Result<Trace, FloorError> {
+    let mut trace = Trace::new();
+    chunks(reader, |chunk, offset| {
+        for (index, &byte) in chunk.iter().enumerate() {
+            trace.follow(byte, offset + index as u64)?;
+        }
+        Ok(ControlFlow::<()>::Continue(()))
+    })?;
+    Ok(trace)
+}
+
+/// When Santa should first go below the ground floor, for [`shortest`] and [`random`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basement {
+    /// At this 1-based position
+    At(u64),
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateError {
+    /// Ending below ground means going through the basement
+    BelowGround(i64),
+    /// Positions start at 1
+    ZeroPosition,
+    /// Getting back down to the ground floor takes as many `)` as `(`, so the basement can
+    /// only be first entered at an odd position
+    EvenPosition(u64),
+    TooLong,
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::BelowGround(floor) => write!(
+                f,
+                "Santa can't end on floor {floor} without entering the basement"
+            ),
+            GenerateError::ZeroPosition => {
+                write!(
+                    f,
+                    "Positions start at 1, so the basement can't be entered at 0"
+                )
+            }
+            GenerateError::EvenPosition(position) => write!(
+                f,
+                "Santa can only first enter the basement at an odd position, not {position}"
+            ),
+            GenerateError::TooLong => write!(f, "The instructions would be too long to count"),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+/// How many characters go before and after the first basement entry, with `extra` more
+/// `()` pairs than needed, checking the target can be met at all.
+fn lengths(floor: i64, basement: Basement, extra: u64) -> Result<(u64, u64), GenerateError> {
+    let padding = extra.checked_mul(2).ok_or(GenerateError::TooLong)?;
+    match basement {
+        Basement::Never if floor < 0 => Err(GenerateError::BelowGround(floor)),
+        Basement::Never => floor
+            .unsigned_abs()
+            .checked_add(padding)
+            .map(|after| (0, after))
+            .ok_or(GenerateError::TooLong),
+        Basement::At(0) => Err(GenerateError::ZeroPosition),
+        Basement::At(position) if position % 2 == 0 => Err(GenerateError::EvenPosition(position)),
+        // From -1 after entering the basement, to `floor`
+        Basement::At(position) => (i128::from(floor) + 1)
+            .unsigned_abs()
+            .try_into()
+            .ok()
+            .and_then(|climb: u64| climb.checked_add(padding))
+            .filter(|after| after.checked_add(position).is_some())
+            .map(|after| (position, after))
+            .ok_or(GenerateError::TooLong),
+    }
+}
+
+/// The shortest instructions that leave Santa on `floor` having first entered the
+/// basement as given: `()` pairs to get to it, then straight up or down.
+///
+/// # Errors
+///
+/// If no instructions can do it, see [`GenerateError`].
+pub fn shortest(floor: i64, basement: Basement) -> Result<String, GenerateError> {
+    let (before, _) = lengths(floor, basement, 0)?;
+    let mut instructions = String::new();
+    let mut from = 0;
+    if before > 0 {
+        instructions.push_str(&"()".repeat(length(before / 2)?));
+        instructions.push(')');
+        from = -1;
+    }
+    let (bracket, steps) = if floor >= from {
+        ('(', floor - from)
+    } else {
+        (')', from - floor)
+    };
+    instructions.extend(std::iter::repeat_n(bracket, length(steps.unsigned_abs())?));
+    Ok(instructions)
+}
+
+/// Random instructions that leave Santa on `floor` having first entered the basement as
+/// given, with `extra` more `(`/`)` pairs than the [`shortest`] after the basement. Every
+/// step is a coin toss between the ones that can still get there, which isn't uniform over
+/// all such instructions but covers them all.
+///
+/// # Errors
+///
+/// If no instructions can do it, see [`GenerateError`].
+pub fn random(
+    floor: i64,
+    basement: Basement,
+    extra: u64,
+    rng: &mut XorShift,
+) -> Result<String, GenerateError> {
+    let (before, after) = lengths(floor, basement, extra)?;
+    let mut instructions = String::with_capacity(length(before + after)?);
+    let mut from = 0;
+    if before > 0 {
+        walk(&mut instructions, 0, 0, before - 1, Some(0), rng);
+        instructions.push(')');
+        from = -1;
+    }
+    let lowest = (basement == Basement::Never).then_some(0);
+    walk(&mut instructions, from, floor, after, lowest, rng);
+    Ok(instructions)
+}
+
+fn length(characters: u64) -> Result<usize, GenerateError> {
+    usize::try_from(characters).map_err(|_| GenerateError::TooLong)
+}
+
+/// Take `steps` random steps from floor `from` to floor `to`, never going below `lowest`.
+/// The caller makes sure it's possible.
+fn walk(out: &mut String, from: i64, to: i64, steps: u64, lowest: Option<i64>, rng: &mut XorShift) {
+    let mut floor = from;
+    for left in (0..steps).rev() {
+        let possible =
+            |next: i64| next.abs_diff(to) <= left && lowest.is_none_or(|lowest| next >= lowest);
+        let up = match (possible(floor + 1), possible(floor - 1)) {
+            (true, true) => rng.chance(1, 2),
+            (up, _) => up,
+        };
+        out.push(if up { '(' } else { ')' });
+        floor += if up { 1 } else { -1 };
+    }
+}
+
+/// The net floors up in `bytes`, or the index of the first byte that isn't a bracket.
+///
+/// # Errors
+///
+/// The index of the first invalid byte.
+pub fn count(bytes: &[u8]) -> Result<i64, usize> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        count_sse2(bytes)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        count_swar(bytes)
+    }
+}
+
+/// One byte at a time, the reference the faster kernels are checked against.
+///
+/// # Errors
+///
+/// The index of the first invalid byte.
+pub fn count_scalar(bytes: &[u8]) -> Result<i64, usize> {
+    let mut net = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' => net += 1,
+            b')' => net -= 1,
+            _ => return Err(index),
+        }
+    }
+    Ok(net)
+}
+
+/// A high bit set in every byte of `word` that equals `byte`, and nowhere else.
+fn matching(word: u64, byte: u8) -> u64 {
+    const LOW: u64 = 0x7F7F_7F7F_7F7F_7F7F;
+    let diff = word ^ (u64::from(byte) * 0x0101_0101_0101_0101);
+    // Adding 0x7F to the low 7 bits of a byte sets its high bit unless they're all zero,
+    // and can't carry into the next byte
+    !(((diff & LOW) + LOW) | diff) & !LOW
+}
+
+/// Eight bytes at a time in a `u64` ("SIMD within a register"), for any platform.
+///
+/// # Errors
+///
+/// The index of the first invalid byte.
+pub fn count_swar(bytes: &[u8]) -> Result<i64, usize> {
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+    let mut net: i64 = 0;
+
+    let words = bytes.chunks_exact(8);
+    let tail = words.remainder();
+    for (index, word) in words.enumerate() {
+        let word = u64::from_le_bytes(word.try_into().unwrap_or_default());
+        let up = matching(word, b'(');
+        let down = matching(word, b')');
+        let invalid = !(up | down) & HIGH;
+        if invalid != 0 {
+            return Err(index * 8 + invalid.trailing_zeros() as usize / 8);
+        }
+        net += i64::from(up.count_ones()) - i64::from(down.count_ones());
+    }
+
+    let done = bytes.len() - tail.len();
+    count_scalar(tail)
+        .map(|rest| net + rest)
+        .map_err(|index| done + index)
+}
+
+/// Sixteen bytes at a time with SSE2, which every `x86_64` processor has.
+///
+/// # Errors
+///
+/// The index of the first invalid byte.
+#[cfg(target_arch = "x86_64")]
+pub fn count_sse2(bytes: &[u8]) -> Result<i64, usize> {
+    // SAFETY: SSE2 is part of the x86_64 baseline, so it's always there to enable
+    unsafe { sse2(bytes) }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+fn sse2(bytes: &[u8]) -> Result<i64, usize> {
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+
+    let mut net: i64 = 0;
+    #[allow(clippy::cast_possible_wrap)] // The bit patterns are what matter
+    let (open, close) = (_mm_set1_epi8(b'(' as i8), _mm_set1_epi8(b')' as i8));
+
+    let blocks = bytes.chunks_exact(16);
+    let tail = blocks.remainder();
+    for (index, block) in blocks.enumerate() {
+        // SAFETY: `block` is exactly 16 readable bytes, and loadu has no alignment needs
+        #[allow(clippy::cast_ptr_alignment)]
+        let block = unsafe { _mm_loadu_si128(block.as_ptr().cast::<__m128i>()) };
+        #[allow(clippy::cast_sign_loss)] // Only the low 16 bits are set
+        let up = _mm_movemask_epi8(_mm_cmpeq_epi8(block, open)) as u32;
+        #[allow(clippy::cast_sign_loss)]
+        let down = _mm_movemask_epi8(_mm_cmpeq_epi8(block, close)) as u32;
+        let invalid = !(up | down) & 0xFFFF;
+        if invalid != 0 {
+            return Err(index * 16 + invalid.trailing_zeros() as usize);
+        }
+        net += i64::from(up.count_ones()) - i64::from(down.count_ones());
+    }
+
+    let done = bytes.len() - tail.len();
+    count_scalar(tail)
+        .map(|rest| net + rest)
+        .map_err(|index| done + index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Kernel = fn(&[u8]) -> Result<i64, usize>;
+
+    fn kernels() -> Vec<(&'static str, Kernel)> {
+        let mut kernels: Vec<(&'static str, Kernel)> = vec![("swar", count_swar), ("count", count)];
+        #[cfg(target_arch = "x86_64")]
+        kernels.push(("sse2", count_sse2));
+        kernels
+    }
+
+    fn brackets(rng: &mut XorShift, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| if rng.chance(1, 2) { b'(' } else { b')' })
+            .collect()
+    }
+
+    #[test]
+    fn test_examples() {
+        for (name, kernel) in kernels() {
+            for (input, want) in [
+                ("(())", 0),
+                ("(((", 3),
+                ("))(((((", 3),
+                (")())())", -3),
+                ("", 0),
+            ] {
+                assert_eq!(kernel(input.as_bytes()), Ok(want), "{name} on {input}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_kernels_match_scalar() {
+        let mut rng = XorShift::new(1);
+        for len in 0..100 {
+            let bytes = brackets(&mut rng, len);
+            let want = count_scalar(&bytes);
+            for (name, kernel) in kernels() {
+                assert_eq!(kernel(&bytes), want, "{name} on {len} bytes");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_byte_at_every_position() {
+        let mut rng = XorShift::new(2);
+        let bytes = brackets(&mut rng, 40);
+        for position in 0..bytes.len() {
+            for bad in [b'\n', b'x', b'(' ^ 0x80, b')' + 1, 0] {
+                let mut bytes = bytes.clone();
+                bytes[position] = bad;
+                // A second bad byte later on mustn't be the one reported
+                if position + 3 < bytes.len() {
+                    bytes[position + 3] = b'?';
+                }
+                for (name, kernel) in kernels() {
+                    assert_eq!(
+                        kernel(&bytes),
+                        Err(position),
+                        "{name} with {bad} at {position}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// A reader that hands out a few bytes at a time and is sometimes interrupted.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        reads: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            if self.reads.is_multiple_of(3) {
+                return Err(ErrorKind::Interrupted.into());
+            }
+            let n = self.bytes.len().min(buffer.len()).min(7);
+            buffer[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut rng = XorShift::new(3);
+        let bytes = brackets(&mut rng, 3 * CHUNK + 123);
+        let want = count_scalar(&bytes).unwrap();
+
+        assert_eq!(floor(bytes.as_slice()).unwrap(), want);
+        let trickle = Trickle {
+            bytes: &bytes[..1000],
+            reads: 0,
+        };
+        assert_eq!(
+            floor(trickle).unwrap(),
+            count_scalar(&bytes[..1000]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stream_reports_absolute_offset() {
+        let mut rng = XorShift::new(4);
+        let mut bytes = brackets(&mut rng, 2 * CHUNK);
+        bytes[CHUNK + 5] = b'\n';
+
+        let err = floor(bytes.as_slice()).unwrap_err();
+        assert!(matches!(
+            err,
+            FloorError::Invalid { byte: b'\n', offset } if offset == CHUNK as u64 + 5
+        ));
+        assert_eq!(err.to_string(), "Invalid character '\\n' at offset 65541");
+    }
+
+    #[test]
+    fn test_basement() {
+        assert_eq!(basement(")".as_bytes()).unwrap(), Some(1));
+        assert_eq!(basement("()())".as_bytes()).unwrap(), Some(5));
+        assert_eq!(basement("(()".as_bytes()).unwrap(), None);
+        // Anything after the basement is never looked at
+        assert_eq!(basement(")oops".as_bytes()).unwrap(), Some(1));
+
+        let mut bytes = vec![b'('; CHUNK + 10];
+        bytes.extend(vec![b')'; CHUNK + 11]);
+        assert_eq!(
+            basement(bytes.as_slice()).unwrap(),
+            Some(2 * CHUNK as u64 + 21)
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let trace = trace("(()))())((((".as_bytes()).unwrap();
+        assert_eq!(trace.floor(), 2);
+        assert_eq!(trace.instructions(), 12);
+        assert_eq!(trace.first_reaching(0), Some(0));
+        assert_eq!(trace.first_reaching(2), Some(2));
+        assert_eq!(trace.first_reaching(-1), Some(5));
+        assert_eq!(trace.first_reaching(-2), Some(8));
+        assert_eq!(trace.first_reaching(3), None);
+        assert_eq!(trace.first_reaching(-3), None);
+        assert_eq!(trace.basement_entries(), [5, 7]);
+        assert_eq!(trace.highest(), (2, 2));
+        assert_eq!(trace.lowest(), (-2, 8));
+        assert_eq!(
+            trace.histogram().collect::<Vec<_>>(),
+            [(-2, 1), (-1, 3), (0, 3), (1, 3), (2, 2)]
+        );
+        assert_eq!(trace.longest_above_ground(), Some(Run { start: 1, end: 3 }));
+    }
+
+    #[test]
+    fn test_trace_edges() {
+        let empty = trace("".as_bytes()).unwrap();
+        assert_eq!(empty.highest(), (0, 0));
+        assert_eq!(empty.lowest(), (0, 0));
+        assert_eq!(empty.histogram().collect::<Vec<_>>(), [(0, 0)]);
+        assert_eq!(empty.longest_above_ground(), None);
+
+        let down = trace(")))(".as_bytes()).unwrap();
+        assert_eq!(down.basement_entries(), [1]);
+        assert_eq!(down.highest(), (0, 0));
+        assert_eq!(down.longest_above_ground(), None);
+
+        // Longer runs win, and ties go to the earliest
+        let ties = trace("()()((".as_bytes()).unwrap();
+        assert_eq!(ties.longest_above_ground(), Some(Run { start: 5, end: 6 }));
+        let ties = trace("()()".as_bytes()).unwrap();
+        assert_eq!(ties.longest_above_ground(), Some(Run { start: 1, end: 1 }));
+        assert!(matches!(
+            trace("(x".as_bytes()),
+            Err(FloorError::Invalid {
+                byte: b'x',
+                offset: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_trace_matches_stream() {
+        let mut rng = XorShift::new(5);
+        let bytes = brackets(&mut rng, CHUNK + 77);
+        let trace = trace(bytes.as_slice()).unwrap();
+        assert_eq!(trace.floor(), floor(bytes.as_slice()).unwrap());
+        assert_eq!(
+            trace.first_reaching(-1),
+            basement(bytes.as_slice()).unwrap()
+        );
+        assert_eq!(
+            trace.histogram().map(|(_, time)| time).sum::<u64>(),
+            trace.instructions()
+        );
+    }
+
+    #[test]
+    fn test_shortest() {
+        assert_eq!(shortest(0, Basement::Never).unwrap(), "");
+        assert_eq!(shortest(3, Basement::Never).unwrap(), "(((");
+        assert_eq!(shortest(-1, Basement::At(1)).unwrap(), ")");
+        assert_eq!(shortest(-3, Basement::At(5)).unwrap(), "()())))");
+        assert_eq!(shortest(2, Basement::At(3)).unwrap(), "())(((");
+    }
+
+    #[test]
+    fn test_infeasible() {
+        assert_eq!(
+            shortest(-1, Basement::Never),
+            Err(GenerateError::BelowGround(-1))
+        );
+        assert_eq!(
+            shortest(5, Basement::At(0)),
+            Err(GenerateError::ZeroPosition)
+        );
+        assert_eq!(
+            random(5, Basement::At(4), 0, &mut XorShift::new(1)),
+            Err(GenerateError::EvenPosition(4))
+        );
+        assert_eq!(
+            random(0, Basement::Never, u64::MAX, &mut XorShift::new(1)),
+            Err(GenerateError::TooLong)
+        );
+        assert_eq!(
+            GenerateError::EvenPosition(4).to_string(),
+            "Santa can only first enter the basement at an odd position, not 4"
+        );
+    }
+
+    #[test]
+    fn test_generated_round_trip() {
+        let mut rng = XorShift::new(6);
+        for _ in 0..500 {
+            #[allow(clippy::cast_possible_wrap)] // Small enough not to
+            let target = rng.between(0..=40) as i64 - 20;
+            let entry = match rng.between(0..=20) {
+                0 if target >= 0 => Basement::Never,
+                position => Basement::At(position * 2 + 1),
+            };
+            let extra = rng.between(0..=10);
+
+            let shortest = shortest(target, entry).unwrap();
+            let random = random(target, entry, extra, &mut rng).unwrap();
+            assert_eq!(random.len() as u64, shortest.len() as u64 + 2 * extra);
+
+            for instructions in [shortest, random] {
+                let want = match entry {
+                    Basement::At(position) => Some(position),
+                    Basement::Never => None,
+                };
+                assert_eq!(floor(instructions.as_bytes()).unwrap(), target);
+                assert_eq!(basement(instructions.as_bytes()).unwrap(), want);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_io_error() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+        let err = floor(Broken).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to read instructions: disk on fire");
+    }
+}
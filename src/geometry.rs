@@ -0,0 +1,387 @@
+//! Points, directions and rectangles on a 2D plane.
+//!
+//! `y` grows downwards, so the first row of a [`Grid`](crate::grid::Grid) is the top and
+//! [`Direction::North`] takes one off `y`.
+
+use anyhow::{anyhow, Error, Result};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// The numbers a [`Point`] or [`Rect`] can be made of, anything that can count in ones.
+pub trait Coord:
+    Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + From<u8>
+{
+}
+
+impl<T> Coord for T where
+    T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + From<u8>
+{
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    pub const fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+}
+
+impl<T: Coord> Point<T> {
+    /// The point one step away in `direction`.
+    #[must_use]
+    pub fn step(self, direction: Direction) -> Point<T> {
+        let one = T::from(1);
+        match direction {
+            Direction::North => Point::new(self.x, self.y - one),
+            Direction::South => Point::new(self.x, self.y + one),
+            Direction::East => Point::new(self.x + one, self.y),
+            Direction::West => Point::new(self.x - one, self.y),
+        }
+    }
+
+    /// The taxicab distance between two points.
+    #[must_use]
+    pub fn manhattan(self, other: Point<T>) -> T {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+    }
+}
+
+fn abs_diff<T: Coord>(a: T, b: T) -> T {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+/// Parse an `x,y` pair.
+impl<T> FromStr for Point<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Point<T>> {
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| anyhow!("Invalid point: {s}"))?;
+        Ok(Point::new(x.trim().parse()?, y.trim().parse()?))
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, rhs: Point<T>) -> Point<T> {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, rhs: Point<T>) -> Point<T> {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Point<T> {
+    type Output = Point<T>;
+
+    fn mul(self, rhs: T) -> Point<T> {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point<T> {
+    type Output = Point<T>;
+
+    fn neg(self) -> Point<T> {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point<T> {
+    fn add_assign(&mut self, rhs: Point<T>) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point<T> {
+    fn sub_assign(&mut self, rhs: Point<T>) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+}
+
+/// Parse a direction from an arrow (`^`, `v`, `>`, `<`) or a compass letter (`N`, `S`, `E`, `W`).
+impl TryFrom<char> for Direction {
+    type Error = Error;
+
+    fn try_from(c: char) -> Result<Direction> {
+        match c {
+            '^' | 'N' => Ok(Direction::North),
+            'v' | 'S' => Ok(Direction::South),
+            '>' | 'E' => Ok(Direction::East),
+            '<' | 'W' => Ok(Direction::West),
+            _ => Err(anyhow!("Invalid direction: {c}")),
+        }
+    }
+}
+
+/// An axis aligned rectangle, inclusive of both corners.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Rect<T> {
+    /// The top left corner
+    pub min: Point<T>,
+    /// The bottom right corner
+    pub max: Point<T>,
+}
+
+impl<T: Coord> Rect<T> {
+    /// The rectangle with `a` and `b` as opposite corners, in either order.
+    pub fn new(a: Point<T>, b: Point<T>) -> Rect<T> {
+        let (min_x, max_x) = if a.x <= b.x { (a.x, b.x) } else { (b.x, a.x) };
+        let (min_y, max_y) = if a.y <= b.y { (a.y, b.y) } else { (b.y, a.y) };
+        Rect {
+            min: Point::new(min_x, min_y),
+            max: Point::new(max_x, max_y),
+        }
+    }
+
+    /// Overflows if the rectangle is wider than `T` can count, see [`Rect::span`] for one
+    /// that can't.
+    #[must_use]
+    pub fn width(&self) -> T {
+        self.max.x - self.min.x + T::from(1)
+    }
+
+    /// Overflows like [`Rect::width`] does.
+    #[must_use]
+    pub fn height(&self) -> T {
+        self.max.y - self.min.y + T::from(1)
+    }
+
+    /// How many points are in the rectangle.
+    #[must_use]
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: Point<T>) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    /// The rectangle covered by both, if they overlap at all.
+    #[must_use]
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let top_left = Point::new(
+            larger(self.min.x, other.min.x),
+            larger(self.min.y, other.min.y),
+        );
+        let bottom_right = Point::new(
+            smaller(self.max.x, other.max.x),
+            smaller(self.max.y, other.max.y),
+        );
+        (top_left.x <= bottom_right.x && top_left.y <= bottom_right.y).then_some(Rect {
+            min: top_left,
+            max: bottom_right,
+        })
+    }
+
+    /// Every point in the rectangle, row by row.
+    #[must_use]
+    pub fn points(&self) -> RectPoints<T> {
+        RectPoints {
+            rect: *self,
+            next: Some(self.min),
+        }
+    }
+}
+
+// PartialOrd only, so std's min and max are out
+fn smaller<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn larger<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+impl Rect<i32> {
+    /// The width and height, which fit in a `u64` for any corners at all.
+    #[must_use]
+    pub fn span(&self) -> (u64, u64) {
+        (
+            u64::from(self.max.x.abs_diff(self.min.x)) + 1,
+            u64::from(self.max.y.abs_diff(self.min.y)) + 1,
+        )
+    }
+}
+
+/// An iterator over the points of a [`Rect`], see [`Rect::points`].
+#[derive(Debug, Clone)]
+pub struct RectPoints<T> {
+    rect: Rect<T>,
+    next: Option<Point<T>>,
+}
+
+impl<T: Coord> Iterator for RectPoints<T> {
+    type Item = Point<T>;
+
+    fn next(&mut self) -> Option<Point<T>> {
+        let current = self.next?;
+        self.next = if current.x < self.rect.max.x {
+            Some(Point::new(current.x + T::from(1), current.y))
+        } else if current.y < self.rect.max.y {
+            Some(Point::new(self.rect.min.x, current.y + T::from(1)))
+        } else {
+            None
+        };
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point_parse() -> Result<()> {
+        assert_eq!("0,0".parse::<Point<u32>>()?, Point::new(0, 0));
+        assert_eq!("660,55".parse::<Point<usize>>()?, Point::new(660, 55));
+        assert_eq!("-3, 4".parse::<Point<i32>>()?, Point::new(-3, 4));
+        assert!("12".parse::<Point<u32>>().is_err());
+        assert!("1,x".parse::<Point<u32>>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_arithmetic() {
+        let a = Point::new(1, 2);
+        let b = Point::new(-4, 6);
+        assert_eq!(a + b, Point::new(-3, 8));
+        assert_eq!(a - b, Point::new(5, -4));
+        assert_eq!(a * 3, Point::new(3, 6));
+        assert_eq!(-a, Point::new(-1, -2));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn test_manhattan() {
+        assert_eq!(Point::new(1, 2).manhattan(Point::new(-4, 6)), 9);
+        assert_eq!(Point::<u32>::new(5, 0).manhattan(Point::new(2, 3)), 6);
+    }
+
+    #[test]
+    fn test_direction() -> Result<()> {
+        let origin = Point::new(0, 0);
+        for (arrow, compass, want) in [
+            ('^', 'N', Point::new(0, -1)),
+            ('v', 'S', Point::new(0, 1)),
+            ('>', 'E', Point::new(1, 0)),
+            ('<', 'W', Point::new(-1, 0)),
+        ] {
+            assert_eq!(Direction::try_from(arrow)?, Direction::try_from(compass)?);
+            assert_eq!(origin.step(Direction::try_from(arrow)?), want);
+        }
+        assert!(Direction::try_from('x').is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rect() {
+        let rect = Rect::new(Point::new(2, 3), Point::new(0, 0));
+        assert_eq!(rect.min, Point::new(0, 0));
+        assert_eq!(rect.max, Point::new(2, 3));
+        assert_eq!(rect.width(), 3);
+        assert_eq!(rect.height(), 4);
+        assert_eq!(rect.area(), 12);
+        assert!(rect.contains(Point::new(2, 3)));
+        assert!(!rect.contains(Point::new(3, 3)));
+
+        let single = Rect::new(Point::new(5u32, 5), Point::new(5, 5));
+        assert_eq!(single.area(), 1);
+
+        assert_eq!(rect.span(), (3, 4));
+        let everything = Rect::new(Point::new(i32::MIN, 0), Point::new(i32::MAX, 0));
+        assert_eq!(everything.span(), (1 << 32, 1));
+    }
+
+    #[test]
+    fn test_rect_points() {
+        let rect = Rect::new(Point::new(0, 0), Point::new(1, 2));
+        let points: Vec<Point<i32>> = rect.points().collect();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(1, 1),
+                Point::new(0, 2),
+                Point::new(1, 2),
+            ]
+        );
+        assert_eq!(rect.points().count(), 6);
+    }
+
+    #[test]
+    fn test_rect_intersection() {
+        let a = Rect::new(Point::new(0, 0), Point::new(4, 4));
+        let b = Rect::new(Point::new(3, 2), Point::new(8, 3));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::new(Point::new(3, 2), Point::new(4, 3)))
+        );
+
+        let c = Rect::new(Point::new(5, 5), Point::new(6, 6));
+        assert_eq!(a.intersection(&c), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! Small weighted graphs, for the puzzles about routes and seating plans.
+//!
+//! Node names are interned to dense indices as they're added, and weights live in an
+//! adjacency matrix, so looking up an edge in a hot loop is just indexing a `Vec`.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The dense index a node name is interned to, from 0 up to the number of nodes.
+pub type NodeId = usize;
+
+/// Weights that can be added up without overflowing silently.
+pub trait CheckedAdd: Sized {
+    /// `self + other`, or `None` if that's too big to hold.
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! checked_add {
+    ($($t:ty),*) => {
+        $(impl CheckedAdd for $t {
+            fn checked_add(self, other: $t) -> Option<$t> {
+                <$t>::checked_add(self, other)
+            }
+        })*
+    };
+}
+
+checked_add!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[derive(Debug, Clone)]
+pub struct Graph<N, W> {
+    directed: bool,
+    names: Vec<N>,
+    ids: HashMap<N, NodeId>,
+    /// `names.len()` squared, row `from` column `to`
+    weights: Vec<Option<W>>,
+}
+
+impl<N, W> Graph<N, W>
+where
+    N: Eq + Hash + Clone,
+    W: Copy,
+{
+    /// A graph where an edge from `a` to `b` says nothing about `b` to `a`.
+    #[must_use]
+    pub fn directed() -> Graph<N, W> {
+        Graph {
+            directed: true,
+            names: Vec::new(),
+            ids: HashMap::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// A graph where every edge goes both ways.
+    #[must_use]
+    pub fn undirected() -> Graph<N, W> {
+        Graph {
+            directed: false,
+            ..Graph::directed()
+        }
+    }
+
+    #[must_use]
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// The number of nodes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The id for `name`, adding it as a new node if it's not been seen before.
+    pub fn intern(&mut self, name: N) -> NodeId {
+        if let Some(&id) = self.ids.get(&name) {
+            return id;
+        }
+
+        let id = self.names.len();
+        self.ids.insert(name.clone(), id);
+        self.names.push(name);
+
+        // Grow the matrix, puzzle graphs are small enough that copying it is fine
+        let size = self.names.len();
+        let mut weights = vec![None; size * size];
+        for from in 0..id {
+            weights[from * size..from * size + id]
+                .copy_from_slice(&self.weights[from * id..(from + 1) * id]);
+        }
+        self.weights = weights;
+
+        id
+    }
+
+    /// The id of an existing node.
+    pub fn id<Q>(&self, name: &Q) -> Option<NodeId>
+    where
+        N: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.ids.get(name).copied()
+    }
+
+    /// # Panics
+    ///
+    /// If `id` isn't a node in this graph.
+    #[must_use]
+    pub fn name(&self, id: NodeId) -> &N {
+        &self.names[id]
+    }
+
+    /// Every node id, in the order they were interned.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.names.len()
+    }
+
+    /// Add (or replace) the edge from `from` to `to`, interning either if needed.
+    ///
+    /// In an undirected graph this also sets the edge back from `to` to `from`.
+    pub fn add_edge(&mut self, from: N, to: N, weight: W) {
+        let from = self.intern(from);
+        let to = self.intern(to);
+        let size = self.names.len();
+
+        self.weights[from * size + to] = Some(weight);
+        if !self.directed {
+            self.weights[to * size + from] = Some(weight);
+        }
+    }
+
+    /// The weight of the edge from `from` to `to`, if there is one.
+    #[must_use]
+    pub fn weight(&self, from: NodeId, to: NodeId) -> Option<W> {
+        let size = self.names.len();
+        if from >= size || to >= size {
+            return None;
+        }
+        self.weights[from * size + to]
+    }
+
+    /// The nodes with an edge from `from`, and the weight of each edge.
+    pub fn neighbours(&self, from: NodeId) -> impl Iterator<Item = (NodeId, W)> + '_ {
+        let size = self.names.len();
+        self.weights
+            .iter()
+            .skip(from * size)
+            .take(if from < size { size } else { 0 })
+            .enumerate()
+            .filter_map(|(to, weight)| weight.map(|w| (to, w)))
+    }
+
+    /// Every edge as `(from, to, weight)`. Undirected edges only come out once, with
+    /// `from <= to`.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, W)> + '_ {
+        self.nodes().flat_map(move |from| {
+            self.neighbours(from)
+                .filter(move |&(to, _)| self.directed || from <= to)
+                .map(move |(to, weight)| (from, to, weight))
+        })
+    }
+
+    /// The total weight of walking `path` in order, or `None` if any leg is missing or the
+    /// total overflows `W`.
+    ///
+    /// A path of fewer than two nodes has no legs, so weighs `W::default()`.
+    #[must_use]
+    pub fn path_weight(&self, path: &[NodeId]) -> Option<W>
+    where
+        W: CheckedAdd + Default,
+    {
+        path.windows(2).try_fold(W::default(), |total, leg| {
+            total.checked_add(self.weight(leg[0], leg[1])?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ireland() -> Graph<&'static str, u32> {
+        let mut graph = Graph::undirected();
+        graph.add_edge("London", "Dublin", 464);
+        graph.add_edge("London", "Belfast", 518);
+        graph.add_edge("Dublin", "Belfast", 141);
+        graph
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut graph: Graph<&str, u32> = Graph::directed();
+        assert!(graph.is_empty());
+        assert_eq!(graph.intern("a"), 0);
+        assert_eq!(graph.intern("b"), 1);
+        assert_eq!(graph.intern("a"), 0);
+        assert_eq!(graph.len(), 2);
+        assert_eq!(graph.id("b"), Some(1));
+        assert_eq!(graph.id("c"), None);
+        assert_eq!(*graph.name(1), "b");
+    }
+
+    #[test]
+    fn test_undirected_weights() {
+        let graph = ireland();
+        let london = graph.id("London").unwrap();
+        let dublin = graph.id("Dublin").unwrap();
+        let belfast = graph.id("Belfast").unwrap();
+
+        assert_eq!(graph.weight(london, dublin), Some(464));
+        assert_eq!(graph.weight(dublin, london), Some(464));
+        assert_eq!(graph.weight(belfast, dublin), Some(141));
+        assert_eq!(graph.weight(london, london), None);
+        assert_eq!(graph.weight(london, 99), None);
+    }
+
+    #[test]
+    fn test_directed_weights() {
+        let mut graph = Graph::directed();
+        graph.add_edge("Alice", "Bob", 54);
+        graph.add_edge("Bob", "Alice", 83);
+        graph.add_edge("Alice", "Carol", -79);
+
+        let alice = graph.id("Alice").unwrap();
+        let bob = graph.id("Bob").unwrap();
+        let carol = graph.id("Carol").unwrap();
+
+        assert_eq!(graph.weight(alice, bob), Some(54));
+        assert_eq!(graph.weight(bob, alice), Some(83));
+        assert_eq!(graph.weight(alice, carol), Some(-79));
+        assert_eq!(graph.weight(carol, alice), None);
+    }
+
+    #[test]
+    fn test_weights_survive_growing() {
+        let mut graph = Graph::directed();
+        graph.add_edge(0, 1, 'a');
+        graph.add_edge(1, 0, 'b');
+        for n in 2..10 {
+            graph.add_edge(n, n - 1, 'c');
+        }
+        assert_eq!(graph.weight(0, 1), Some('a'));
+        assert_eq!(graph.weight(1, 0), Some('b'));
+        assert_eq!(graph.weight(9, 8), Some('c'));
+        assert_eq!(graph.weight(8, 9), None);
+    }
+
+    #[test]
+    fn test_neighbours_and_edges() {
+        let graph = ireland();
+        let dublin = graph.id("Dublin").unwrap();
+        let mut neighbours: Vec<(&str, u32)> = graph
+            .neighbours(dublin)
+            .map(|(id, w)| (*graph.name(id), w))
+            .collect();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, vec![("Belfast", 141), ("London", 464)]);
+
+        assert_eq!(graph.edges().count(), 3);
+        assert_eq!(
+            graph.edges().map(|(_, _, w)| w).sum::<u32>(),
+            464 + 518 + 141
+        );
+    }
+
+    #[test]
+    fn test_path_weight() {
+        let graph = ireland();
+        let route: Vec<NodeId> = ["London", "Dublin", "Belfast"]
+            .iter()
+            .map(|name| graph.id(*name).unwrap())
+            .collect();
+        assert_eq!(graph.path_weight(&route), Some(605));
+        assert_eq!(graph.path_weight(&route[..1]), Some(0));
+
+        let mut sparse = Graph::undirected();
+        sparse.add_edge("a", "b", 1);
+        sparse.intern("c");
+        assert_eq!(sparse.path_weight(&[0, 1, 2]), None);
+
+        let mut long = Graph::undirected();
+        long.add_edge("a", "b", u32::MAX);
+        long.add_edge("b", "c", 1);
+        assert_eq!(long.path_weight(&[0, 1]), Some(u32::MAX));
+        assert_eq!(long.path_weight(&[0, 1, 2]), None);
+    }
+}
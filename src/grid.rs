@@ -0,0 +1,453 @@
+//! A dense, fixed size 2D grid for the puzzles that live on one.
+
+use crate::geometry::{Point, Rect};
+use anyhow::{anyhow, Result};
+use std::ops::{Index, IndexMut};
+
+const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const NEIGHBOURS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+/// A `width` x `height` grid, stored row by row in a single `Vec`.
+///
+/// Cells are indexed by a point with `x` across the columns and `y` down the rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Make a grid with every cell set to `fill`.
+    #[must_use]
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Make a grid from its cells, given row by row.
+    ///
+    /// # Errors
+    ///
+    /// If the number of cells isn't `width * height`.
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Result<Grid<T>> {
+        if cells.len() != width * height {
+            return Err(anyhow!(
+                "{} cells can't make a {width}x{height} grid",
+                cells.len()
+            ));
+        }
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parse a grid with one line per row, turning each character into a cell with `cell`.
+    ///
+    /// # Errors
+    ///
+    /// If the rows aren't all the same length, or `cell` fails on any character.
+    pub fn parse<F>(input: &str, cell: F) -> Result<Grid<T>>
+    where
+        F: Fn(char) -> Result<T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+
+        for (y, line) in input.trim().lines().enumerate() {
+            let before = cells.len();
+            for c in line.chars() {
+                cells.push(cell(c)?);
+            }
+            let row_width = cells.len() - before;
+            match width {
+                None => width = Some(row_width),
+                Some(w) if w != row_width => {
+                    return Err(anyhow!("Row {y} has {row_width} cells, expected {w}"));
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        Grid::from_vec(width.unwrap_or(0), height, cells)
+    }
+
+    /// Render the grid with one line per row, turning each cell into a character with `cell`.
+    pub fn render<F>(&self, cell: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            out.extend(row.iter().map(&cell));
+            out.push('\n');
+        }
+        out
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The total number of cells.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: Point<usize>) -> bool {
+        point.x < self.width && point.y < self.height
+    }
+
+    fn offset(&self, point: Point<usize>) -> Option<usize> {
+        self.contains(point)
+            .then_some(point.y * self.width + point.x)
+    }
+
+    #[must_use]
+    pub fn get(&self, point: Point<usize>) -> Option<&T> {
+        self.offset(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point<usize>) -> Option<&mut T> {
+        self.offset(point).map(|i| &mut self.cells[i])
+    }
+
+    /// Every cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// Every point in the grid, row by row, in the same order as [`Grid::iter`].
+    pub fn points(&self) -> impl Iterator<Item = Point<usize>> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    /// # Panics
+    ///
+    /// If `y` is out of bounds.
+    #[must_use]
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "Row {y} out of bounds");
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks_exact panics on 0, an empty grid has no cells to chunk anyway
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    /// # Panics
+    ///
+    /// If `x` is out of bounds.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column {x} out of bounds");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    fn neighbours<'a>(
+        &self,
+        point: Point<usize>,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Point<usize>> + 'a {
+        let (width, height) = (self.width, self.height);
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let x = point.x.checked_add_signed(dx)?;
+            let y = point.y.checked_add_signed(dy)?;
+            (x < width && y < height).then_some(Point::new(x, y))
+        })
+    }
+
+    /// The in bounds points directly above, right, below and left of `point`.
+    pub fn neighbours4(&self, point: Point<usize>) -> impl Iterator<Item = Point<usize>> {
+        self.neighbours(point, &NEIGHBOURS_4)
+    }
+
+    /// The in bounds points surrounding `point`, diagonals included.
+    pub fn neighbours8(&self, point: Point<usize>) -> impl Iterator<Item = Point<usize>> {
+        self.neighbours(point, &NEIGHBOURS_8)
+    }
+
+    fn check_view(&self, rect: Rect<usize>) {
+        assert!(
+            self.contains(rect.max),
+            "View {rect:?} doesn't fit in a {}x{} grid",
+            self.width,
+            self.height
+        );
+    }
+
+    /// A view of the cells inside `rect`.
+    ///
+    /// # Panics
+    ///
+    /// If `rect` runs off the grid.
+    #[must_use]
+    pub fn view(&self, rect: Rect<usize>) -> View<'_, T> {
+        self.check_view(rect);
+        View {
+            grid: self,
+            origin: rect.min,
+            width: rect.width(),
+            height: rect.height(),
+        }
+    }
+
+    /// A mutable view of the cells inside `rect`.
+    ///
+    /// # Panics
+    ///
+    /// If `rect` runs off the grid.
+    pub fn view_mut(&mut self, rect: Rect<usize>) -> ViewMut<'_, T> {
+        self.check_view(rect);
+        ViewMut {
+            origin: rect.min,
+            width: rect.width(),
+            height: rect.height(),
+            grid: self,
+        }
+    }
+}
+
+impl<T> Index<Point<usize>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point<usize>) -> &T {
+        self.get(point)
+            .unwrap_or_else(|| panic!("{point:?} out of bounds"))
+    }
+}
+
+impl<T> IndexMut<Point<usize>> for Grid<T> {
+    fn index_mut(&mut self, point: Point<usize>) -> &mut T {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("{point:?} out of bounds"))
+    }
+}
+
+/// A rectangular window onto part of a [`Grid`], indexed from its own top left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    origin: Point<usize>,
+    width: usize,
+    height: usize,
+}
+
+impl<'a, T> View<'a, T> {
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn get(&self, point: Point<usize>) -> Option<&'a T> {
+        if point.x >= self.width || point.y >= self.height {
+            return None;
+        }
+        self.grid.get(self.origin + point)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let (x, width) = (self.origin.x, self.width);
+        self.grid
+            .rows()
+            .skip(self.origin.y)
+            .take(self.height)
+            .map(move |row| &row[x..x + width])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> {
+        self.rows().flatten()
+    }
+}
+
+/// A mutable rectangular window onto part of a [`Grid`].
+#[derive(Debug)]
+pub struct ViewMut<'a, T> {
+    grid: &'a mut Grid<T>,
+    origin: Point<usize>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> ViewMut<'_, T> {
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        let (x, width) = (self.origin.x, self.width);
+        self.grid
+            .cells
+            .chunks_exact_mut(self.grid.width.max(1))
+            .skip(self.origin.y)
+            .take(self.height)
+            .map(move |row| &mut row[x..x + width])
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.rows_mut().flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn digits() -> Grid<u32> {
+        Grid::parse("123\n456\n789", |c| {
+            c.to_digit(10).ok_or_else(|| anyhow!("Not a digit: {c}"))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let grid = digits();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid.len(), 9);
+        assert_eq!(
+            grid.render(|d| char::from_digit(*d, 10).unwrap()),
+            "123\n456\n789\n"
+        );
+
+        assert!(Grid::parse("12\n345", Ok).is_err());
+        assert!(Grid::parse("12\n3x", |c| c.to_digit(10).ok_or_else(|| anyhow!("{c}"))).is_err());
+    }
+
+    #[test]
+    fn test_from_vec() {
+        assert!(Grid::from_vec(2, 2, vec![1, 2, 3, 4]).is_ok());
+        assert!(Grid::from_vec(2, 2, vec![1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_index() {
+        let mut grid = digits();
+        assert_eq!(grid[Point::new(0, 0)], 1);
+        assert_eq!(grid[Point::new(2, 1)], 6);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+
+        grid[Point::new(1, 1)] = 0;
+        assert_eq!(grid.row(1), &[4, 0, 6]);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = digits();
+        let rows: Vec<&[u32]> = grid.rows().collect();
+        assert_eq!(rows, vec![&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]);
+
+        let columns: Vec<Vec<u32>> = grid.columns().map(|c| c.copied().collect()).collect();
+        assert_eq!(columns, vec![vec![1, 4, 7], vec![2, 5, 8], vec![3, 6, 9]]);
+    }
+
+    #[test]
+    fn test_points_match_iter() {
+        let grid = digits();
+        for (point, value) in grid.points().zip(grid.iter()) {
+            assert_eq!(grid[point], *value);
+        }
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = digits();
+        let values =
+            |points: Vec<Point<usize>>| points.iter().map(|p| grid[*p]).collect::<Vec<_>>();
+
+        assert_eq!(
+            values(grid.neighbours4(Point::new(1, 1)).collect()),
+            vec![2, 6, 8, 4]
+        );
+        assert_eq!(
+            values(grid.neighbours4(Point::new(0, 0)).collect()),
+            vec![2, 4]
+        );
+        assert_eq!(
+            values(grid.neighbours8(Point::new(1, 1)).collect()),
+            vec![1, 2, 3, 6, 9, 8, 7, 4]
+        );
+        assert_eq!(
+            values(grid.neighbours8(Point::new(2, 2)).collect()),
+            vec![5, 6, 8]
+        );
+    }
+
+    #[test]
+    fn test_views() {
+        let mut grid = digits();
+        let view = grid.view(Rect::new(Point::new(1, 1), Point::new(2, 2)));
+        assert_eq!((view.width(), view.height()), (2, 2));
+        assert_eq!(view.get(Point::new(0, 0)), Some(&5));
+        assert_eq!(view.get(Point::new(2, 0)), None);
+        assert_eq!(view.iter().copied().collect::<Vec<_>>(), vec![5, 6, 8, 9]);
+
+        for cell in grid
+            .view_mut(Rect::new(Point::new(0, 0), Point::new(1, 0)))
+            .iter_mut()
+        {
+            *cell = 0;
+        }
+        assert_eq!(grid.row(0), &[0, 0, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn test_view_out_of_bounds() {
+        let grid = digits();
+        let _ = grid.view(Rect::new(Point::new(1, 1), Point::new(3, 3)));
+    }
+}
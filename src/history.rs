@@ -0,0 +1,295 @@
+//! A persistent log of every solve the runner does, so that a refactor that slows a day
+//! down or changes an answer shows up over time.
+//!
+//! Only plain runs are logged, not ones given extra arguments for a day. Each run is timed
+//! as a whole process, so both parts of a day share the one duration.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write as _};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// At the top of the repo, wherever the runner is started from.
+const HISTORY_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/history.tsv");
+
+/// How many of the most recent runs make it into a sparkline.
+const SPARKLINE_WIDTH: usize = 30;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    /// Seconds since the unix epoch
+    timestamp: u64,
+    commit: Option<String>,
+    day: usize,
+    part: usize,
+    answer: String,
+    duration: Duration,
+}
+
+impl Record {
+    fn parse(line: &str) -> Result<Record> {
+        let parts: Vec<&str> = line.split('\t').collect();
+        match parts.as_slice() {
+            [timestamp, commit, day, part, answer, micros] => Ok(Record {
+                timestamp: timestamp.parse()?,
+                commit: (*commit != "-").then(|| (*commit).to_string()),
+                day: day.parse()?,
+                part: part.parse()?,
+                answer: (*answer).to_string(),
+                duration: Duration::from_micros(micros.parse()?),
+            }),
+            _ => Err(anyhow!("Invalid history line: {line}")),
+        }
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.timestamp,
+            self.commit.as_deref().unwrap_or("-"),
+            self.day,
+            self.part,
+            self.answer,
+            self.duration.as_micros()
+        )
+    }
+}
+
+/// Pull the `Part N: answer` lines out of a day's output.
+fn answers(output: &str) -> Vec<(usize, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (part, answer) = line.strip_prefix("Part ")?.split_once(": ")?;
+            Some((part.parse().ok()?, answer.trim().to_string()))
+        })
+        .collect()
+}
+
+fn current_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let commit = String::from_utf8(output.stdout).ok()?;
+    Some(commit.trim().to_string())
+}
+
+/// Append every answer in a day's output to the history file.
+///
+/// The binaries don't time their parts individually, so each part is recorded with
+/// the duration of the whole run.
+pub fn record(day: usize, output: &str, duration: Duration) -> Result<()> {
+    let answers = answers(output);
+    if answers.is_empty() {
+        return Ok(());
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let commit = current_commit();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(HISTORY_FILE)?;
+
+    for (part, answer) in answers {
+        let record = Record {
+            timestamp,
+            commit: commit.clone(),
+            day,
+            part,
+            answer,
+            duration,
+        };
+        writeln!(file, "{}", record.format())?;
+    }
+
+    Ok(())
+}
+
+fn load() -> Result<Vec<Record>> {
+    let contents = match fs::read_to_string(HISTORY_FILE) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    contents.lines().map(Record::parse).collect()
+}
+
+fn sparkline(durations: &[Duration]) -> String {
+    let min = durations.iter().min().copied().unwrap_or_default();
+    let max = durations.iter().max().copied().unwrap_or_default();
+    let range = max.saturating_sub(min).as_micros();
+    let top = SPARKS.len() - 1;
+
+    durations
+        .iter()
+        .map(|d| {
+            if range == 0 {
+                return SPARKS[0];
+            }
+            // Round to the nearest spark, in integer micros to dodge float casts
+            let offset = d.saturating_sub(min).as_micros();
+            let index = (offset * top as u128 + range / 2) / range;
+            SPARKS[usize::try_from(index).unwrap_or(top)]
+        })
+        .collect()
+}
+
+/// Render a unix timestamp as a UTC `YYYY-MM-DD HH:MM`.
+fn format_timestamp(timestamp: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm, shifted so eras start on March 1st
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    let seconds = timestamp % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+fn describe(record: &Record) -> String {
+    let when = format_timestamp(record.timestamp);
+    match &record.commit {
+        Some(commit) => format!("{when} ({commit})"),
+        None => when,
+    }
+}
+
+/// Print timing trends and answer changes for every day (or just one) in the history.
+pub fn show(day: Option<usize>) -> Result<()> {
+    let mut runs: BTreeMap<(usize, usize), Vec<Record>> = BTreeMap::new();
+    for record in load()? {
+        if day.is_none_or(|day| day == record.day) {
+            runs.entry((record.day, record.part))
+                .or_default()
+                .push(record);
+        }
+    }
+
+    if runs.is_empty() {
+        println!("No history yet!");
+        return Ok(());
+    }
+
+    let mut report = String::new();
+    for ((day, part), records) in &runs {
+        let durations: Vec<Duration> = records.iter().map(|r| r.duration).collect();
+        let recent = &durations[durations.len().saturating_sub(SPARKLINE_WIDTH)..];
+        let min = durations.iter().min().copied().unwrap_or_default();
+        let max = durations.iter().max().copied().unwrap_or_default();
+        let last = durations.last().copied().unwrap_or_default();
+
+        writeln!(
+            report,
+            "Day {day:02} Part {part}  {}  last {last:.2?} (min {min:.2?}, max {max:.2?}, {} runs)",
+            sparkline(recent),
+            durations.len()
+        )?;
+
+        let first = &records[0];
+        writeln!(
+            report,
+            "  answer {} since {}",
+            first.answer,
+            describe(first)
+        )?;
+        for (before, after) in records.iter().zip(records.iter().skip(1)) {
+            if before.answer != after.answer {
+                writeln!(
+                    report,
+                    "  answer changed {} -> {} at {}",
+                    before.answer,
+                    after.answer,
+                    describe(after)
+                )?;
+            }
+        }
+    }
+
+    print!("{report}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_answers() {
+        assert_eq!(
+            answers("Part 1: 280\nPart 2: 1797\n"),
+            vec![(1, "280".to_string()), (2, "1797".to_string())]
+        );
+        assert_eq!(
+            answers("Part 1: hxbxxyzz"),
+            vec![(1, "hxbxxyzz".to_string())]
+        );
+        assert!(answers("Day 13 not solved!").is_empty());
+    }
+
+    #[test]
+    fn test_record_round_trip() -> Result<()> {
+        let record = Record {
+            timestamp: 1_700_000_000,
+            commit: Some("abc1234".to_string()),
+            day: 6,
+            part: 2,
+            answer: "14110788".to_string(),
+            duration: Duration::from_micros(1_234_567),
+        };
+        assert_eq!(Record::parse(&record.format())?, record);
+
+        let record = Record {
+            commit: None,
+            ..record
+        };
+        assert_eq!(Record::parse(&record.format())?, record);
+
+        assert!(Record::parse("not a record").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let durations: Vec<Duration> = (0..8).map(Duration::from_millis).collect();
+        assert_eq!(sparkline(&durations), "▁▂▃▄▅▆▇█");
+
+        let flat = vec![Duration::from_millis(5); 3];
+        assert_eq!(sparkline(&flat), "▁▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13");
+    }
+}
@@ -0,0 +1,63 @@
+//! Netpbm images, greyscale PGM and colour PPM, for pictures of a [`Grid`].
+//!
+//! Both are a short text header then a byte (or three) per cell, so they need no
+//! dependencies and open in most image viewers.
+
+use crate::grid::Grid;
+
+/// A colour as red, green and blue.
+pub type Rgb = [u8; 3];
+
+/// A binary (`P5`) PGM, a cell per pixel with 0 black and 255 white.
+#[must_use]
+pub fn pgm(grid: &Grid<u8>) -> Vec<u8> {
+    let mut image = header("P5", grid.width(), grid.height());
+    image.extend(grid.iter());
+    image
+}
+
+/// A binary (`P6`) PPM, a cell per pixel.
+#[must_use]
+pub fn ppm(grid: &Grid<Rgb>) -> Vec<u8> {
+    let mut image = header("P6", grid.width(), grid.height());
+    image.extend(grid.iter().flatten());
+    image
+}
+
+fn header(magic: &str, width: usize, height: usize) -> Vec<u8> {
+    format!("{magic}\n{width} {height}\n255\n").into_bytes()
+}
+
+/// A ramp from black through red and yellow to white, for `level` from 0 to 255.
+#[must_use]
+pub fn heat(level: u8) -> Rgb {
+    // Three times the range, filling one channel after another
+    let scaled = u32::from(level) * 3;
+    let channel = |from: u32| u8::try_from(scaled.saturating_sub(from).min(255)).unwrap_or(255);
+    [channel(0), channel(255), channel(510)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pgm() {
+        let grid = Grid::from_vec(3, 2, vec![0, 1, 2, 3, 4, 255]).unwrap();
+        assert_eq!(pgm(&grid), b"P5\n3 2\n255\n\x00\x01\x02\x03\x04\xff");
+    }
+
+    #[test]
+    fn test_ppm() {
+        let grid = Grid::from_vec(2, 1, vec![[1, 2, 3], [4, 5, 6]]).unwrap();
+        assert_eq!(ppm(&grid), b"P6\n2 1\n255\n\x01\x02\x03\x04\x05\x06");
+    }
+
+    #[test]
+    fn test_heat() {
+        assert_eq!(heat(0), [0, 0, 0]);
+        assert_eq!(heat(85), [255, 0, 0]);
+        assert_eq!(heat(170), [255, 255, 0]);
+        assert_eq!(heat(255), [255, 255, 255]);
+    }
+}
@@ -0,0 +1,506 @@
+//! Choosing items to pack under two limits at once, volume and weight, for the most value.
+//!
+//! [`plan`] solves small instances exactly with dynamic programming over every pair of
+//! remaining capacities. Bigger ones go to a depth first branch and bound, which starts
+//! from the [`greedy`] answer and gives up after a fixed number of nodes. In that case the
+//! plan is still valid, just not proven the best.
+
+use crate::bitset::{BitSet, Bits};
+use std::cmp::Ordering;
+
+/// The most memory, in bytes, [`plan`] lets [`dynamic`] use for its two tables: a `u128`
+/// per capacity pair, and a bit per item per capacity pair.
+const DYNAMIC_BYTES: u64 = 64_000_000;
+
+/// How many nodes branch and bound explores before settling for the best plan so far.
+const NODE_BUDGET: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Item {
+    pub volume: u64,
+    pub weight: u64,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub volume: u64,
+    pub weight: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Dynamic,
+    BranchAndBound,
+    Greedy,
+}
+
+/// The items chosen, and how much of each limit they use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// Indices into the items, in increasing order
+    pub chosen: Vec<usize>,
+    /// Summed in a `u128`, since item values can each be up to `u64::MAX`
+    pub value: u128,
+    pub volume: u64,
+    pub weight: u64,
+    pub method: Method,
+    /// Whether no other plan could have more value
+    pub optimal: bool,
+}
+
+impl Plan {
+    fn new(items: &[Item], mut chosen: Vec<usize>, method: Method, optimal: bool) -> Plan {
+        chosen.sort_unstable();
+        Plan {
+            value: chosen.iter().map(|&i| u128::from(items[i].value)).sum(),
+            volume: chosen.iter().map(|&i| items[i].volume).sum(),
+            weight: chosen.iter().map(|&i| items[i].weight).sum(),
+            chosen,
+            method,
+            optimal,
+        }
+    }
+}
+
+/// The best plan that can be found for `items` under `limits`, exactly where that's cheap
+/// enough.
+#[must_use]
+pub fn plan(items: &[Item], limits: Limits) -> Plan {
+    let pairs = limits
+        .volume
+        .saturating_add(1)
+        .checked_mul(limits.weight.saturating_add(1));
+    let bytes = pairs.and_then(|pairs| {
+        let taken = pairs.checked_mul(items.len() as u64)?.div_ceil(8);
+        pairs.checked_mul(16)?.checked_add(taken)
+    });
+
+    match bytes {
+        Some(bytes) if bytes <= DYNAMIC_BYTES => dynamic(items, limits),
+        _ => branch_and_bound(items, limits, NODE_BUDGET),
+    }
+}
+
+/// The optimal plan, by filling in the best value for every pair of capacities.
+///
+/// Takes time proportional to `items * volume * weight`, and memory for a `u128` per
+/// capacity pair plus a bit per item per pair. See [`plan`] for when that's too much.
+///
+/// # Panics
+///
+/// If the limits are too large to index a table by.
+#[must_use]
+pub fn dynamic(items: &[Item], limits: Limits) -> Plan {
+    let volumes = usize::try_from(limits.volume).expect("Volume limit too large") + 1;
+    let weights = usize::try_from(limits.weight).expect("Weight limit too large") + 1;
+    let cells = volumes * weights;
+
+    // best[v * weights + w] is the most value fitting in volume v and weight w so far, and
+    // a bit in `taken` records each time an item improved a cell
+    let mut best = vec![0u128; cells];
+    let mut taken = BitSet::with_capacity(items.len() * cells);
+
+    for (i, item) in items.iter().enumerate() {
+        let (Ok(volume), Ok(weight)) = (usize::try_from(item.volume), usize::try_from(item.weight))
+        else {
+            continue;
+        };
+        if volume >= volumes || weight >= weights {
+            continue;
+        }
+        // Backwards, so each item is only counted once per cell
+        for v in (volume..volumes).rev() {
+            for w in (weight..weights).rev() {
+                let with = best[(v - volume) * weights + w - weight] + u128::from(item.value);
+                if with > best[v * weights + w] {
+                    best[v * weights + w] = with;
+                    taken.insert(i * cells + v * weights + w);
+                }
+            }
+        }
+    }
+
+    let mut chosen = Vec::new();
+    let (mut v, mut w) = (volumes - 1, weights - 1);
+    for (i, item) in items.iter().enumerate().rev() {
+        if taken.contains(i * cells + v * weights + w) {
+            chosen.push(i);
+            // Only items that fit were ever taken, so these can't underflow
+            v -= usize::try_from(item.volume).unwrap_or(v);
+            w -= usize::try_from(item.weight).unwrap_or(w);
+        }
+    }
+
+    Plan::new(items, chosen, Method::Dynamic, true)
+}
+
+/// How much an item is worth for the share of the limits it uses, bigger is better.
+fn density(item: &Item, limits: Limits) -> f64 {
+    #[allow(clippy::cast_precision_loss)] // Only for ordering, so close is good enough
+    let share = item.volume as f64 / limits.volume.max(1) as f64
+        + item.weight as f64 / limits.weight.max(1) as f64;
+    #[allow(clippy::cast_precision_loss)]
+    let value = item.value as f64;
+    if share == 0.0 {
+        f64::INFINITY
+    } else {
+        value / share
+    }
+}
+
+/// `items` indices ordered best value for their size first.
+fn by_density(items: &[Item], limits: Limits) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| {
+        density(&items[b], limits)
+            .total_cmp(&density(&items[a], limits))
+            .then(a.cmp(&b))
+    });
+    order
+}
+
+/// The volume and weight used once `item` is added, if it still fits in `limits`.
+fn add(volume: u64, weight: u64, item: &Item, limits: Limits) -> Option<(u64, u64)> {
+    let volume = volume
+        .checked_add(item.volume)
+        .filter(|&volume| volume <= limits.volume)?;
+    let weight = weight
+        .checked_add(item.weight)
+        .filter(|&weight| weight <= limits.weight)?;
+    Some((volume, weight))
+}
+
+/// Take items best value for their size first, whenever they still fit. Fast, and often
+/// close, but never guaranteed to be the best.
+#[must_use]
+pub fn greedy(items: &[Item], limits: Limits) -> Plan {
+    let mut chosen = Vec::new();
+    let (mut volume, mut weight) = (0, 0);
+    for i in by_density(items, limits) {
+        if let Some(used) = add(volume, weight, &items[i], limits) {
+            (volume, weight) = used;
+            chosen.push(i);
+        }
+    }
+    Plan::new(items, chosen, Method::Greedy, false)
+}
+
+/// Depth first search over taking or leaving each item, pruning any branch that can't beat
+/// the best plan found so far. Stops after `budget` nodes, and the plan is only marked
+/// optimal if the search finished.
+#[must_use]
+pub fn branch_and_bound(items: &[Item], limits: Limits, budget: u64) -> Plan {
+    let incumbent = greedy(items, limits);
+    let order = by_density(items, limits);
+
+    let mut search = Bound {
+        items,
+        limits,
+        by_volume: ratio_order(items, &order, |item| item.volume),
+        by_weight: ratio_order(items, &order, |item| item.weight),
+        order,
+        taken: vec![false; items.len()],
+        best_value: incumbent.value,
+        best: None,
+        nodes: 0,
+        budget,
+    };
+    search.explore(0, 0, 0, 0);
+
+    let finished = search.nodes <= search.budget;
+    match search.best {
+        Some(taken) => {
+            let chosen = (0..items.len())
+                .filter(|&depth| taken[depth])
+                .map(|depth| search.order[depth])
+                .collect();
+            Plan::new(items, chosen, Method::BranchAndBound, finished)
+        }
+        None => Plan {
+            method: Method::BranchAndBound,
+            optimal: finished,
+            ..incumbent
+        },
+    }
+}
+
+/// Depths in the search (positions in `order`) sorted by value per unit of `size`, best
+/// first, for the fractional bound on that one limit.
+fn ratio_order(items: &[Item], order: &[usize], size: impl Fn(&Item) -> u64) -> Vec<usize> {
+    let mut depths: Vec<usize> = (0..order.len()).collect();
+    depths.sort_by(|&a, &b| {
+        let (a, b) = (&items[order[a]], &items[order[b]]);
+        // a.value / size(a) against b.value / size(b), without dividing
+        let lhs = u128::from(b.value) * u128::from(size(a));
+        let rhs = u128::from(a.value) * u128::from(size(b));
+        lhs.cmp(&rhs)
+    });
+    depths
+}
+
+struct Bound<'a> {
+    items: &'a [Item],
+    limits: Limits,
+    /// Item indices in the order they're decided
+    order: Vec<usize>,
+    by_volume: Vec<usize>,
+    by_weight: Vec<usize>,
+    /// Whether the item at each depth is taken on the current branch
+    taken: Vec<bool>,
+    best_value: u128,
+    /// `taken` for the best plan, if it's better than the greedy one
+    best: Option<Vec<bool>>,
+    nodes: u64,
+    budget: u64,
+}
+
+impl Bound<'_> {
+    fn explore(&mut self, depth: usize, value: u128, volume: u64, weight: u64) {
+        self.nodes += 1;
+        if self.nodes > self.budget {
+            return;
+        }
+        if value > self.best_value {
+            self.best_value = value;
+            self.best = Some(self.taken.clone());
+        }
+        if depth == self.order.len() || self.bound(depth, value, volume, weight) <= self.best_value
+        {
+            return;
+        }
+
+        let item = self.items[self.order[depth]];
+        if let Some((with_volume, with_weight)) = add(volume, weight, &item, self.limits) {
+            self.taken[depth] = true;
+            self.explore(
+                depth + 1,
+                value + u128::from(item.value),
+                with_volume,
+                with_weight,
+            );
+            self.taken[depth] = false;
+        }
+        self.explore(depth + 1, value, volume, weight);
+    }
+
+    /// The most value the rest of this branch could possibly add up to: the smaller of the
+    /// fractional knapsack bounds on volume alone and on weight alone.
+    fn bound(&self, depth: usize, value: u128, volume: u64, weight: u64) -> u128 {
+        let by_volume = self.fractional(&self.by_volume, depth, self.limits.volume - volume, |i| {
+            i.volume
+        });
+        let by_weight = self.fractional(&self.by_weight, depth, self.limits.weight - weight, |i| {
+            i.weight
+        });
+        value + by_volume.min(by_weight)
+    }
+
+    /// Fill `room` with the undecided items best ratio first, taking a fraction of the
+    /// first one that doesn't fit.
+    fn fractional(
+        &self,
+        ratio: &[usize],
+        depth: usize,
+        mut room: u64,
+        size: impl Fn(&Item) -> u64,
+    ) -> u128 {
+        let mut total: u128 = 0;
+        for &d in ratio.iter().filter(|&&d| d >= depth) {
+            let item = &self.items[self.order[d]];
+            let item_size = size(item);
+            match item_size.cmp(&room) {
+                Ordering::Less | Ordering::Equal => {
+                    room -= item_size;
+                    total += u128::from(item.value);
+                }
+                Ordering::Greater => {
+                    // Rounded up so the bound stays an overestimate
+                    let part =
+                        (u128::from(item.value) * u128::from(room)).div_ceil(u128::from(item_size));
+                    return total + part;
+                }
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn item(volume: u64, weight: u64, value: u64) -> Item {
+        Item {
+            volume,
+            weight,
+            value,
+        }
+    }
+
+    fn feasible(items: &[Item], limits: Limits, plan: &Plan) -> bool {
+        let volume: u64 = plan.chosen.iter().map(|&i| items[i].volume).sum();
+        let weight: u64 = plan.chosen.iter().map(|&i| items[i].weight).sum();
+        volume == plan.volume
+            && weight == plan.weight
+            && volume <= limits.volume
+            && weight <= limits.weight
+    }
+
+    #[test]
+    fn test_weight_is_the_tight_limit() {
+        // The three light items fill the volume between them, but the heavy one is worth
+        // more and still leaves room for one of them
+        let items = [item(3, 1, 4), item(3, 1, 4), item(3, 1, 4), item(2, 9, 13)];
+        let limits = Limits {
+            volume: 9,
+            weight: 10,
+        };
+
+        let exact = dynamic(&items, limits);
+        assert_eq!(exact.chosen.len(), 2);
+        assert!(exact.chosen.contains(&3));
+        assert_eq!(exact.value, 17);
+        assert!(exact.optimal);
+
+        let searched = branch_and_bound(&items, limits, 1000);
+        assert_eq!(searched.value, 17);
+        assert!(searched.optimal);
+    }
+
+    #[test]
+    fn test_nothing_fits() {
+        let items = [item(10, 1, 5), item(1, 10, 5)];
+        let limits = Limits {
+            volume: 5,
+            weight: 5,
+        };
+        for plan in [
+            dynamic(&items, limits),
+            branch_and_bound(&items, limits, 100),
+        ] {
+            assert!(plan.chosen.is_empty());
+            assert_eq!(plan.value, 0);
+        }
+        assert_eq!(plan(&[], limits).value, 0);
+    }
+
+    #[test]
+    fn test_plan_picks_a_method() {
+        let items = [item(1, 1, 1), item(2, 2, 2)];
+        let small = Limits {
+            volume: 3,
+            weight: 3,
+        };
+        assert_eq!(plan(&items, small).method, Method::Dynamic);
+
+        let huge = Limits {
+            volume: 1 << 40,
+            weight: 1 << 40,
+        };
+        let searched = plan(&items, huge);
+        assert_eq!(searched.method, Method::BranchAndBound);
+        assert_eq!(searched.value, 3);
+
+        // One item, but 8001 * 8001 capacity pairs would be 512MB of u64s on their own
+        let wide = Limits {
+            volume: 8000,
+            weight: 8000,
+        };
+        assert_eq!(plan(&[item(1, 1, 1)], wide).method, Method::BranchAndBound);
+    }
+
+    #[test]
+    fn test_sizes_near_the_top_of_u64() {
+        // The first two overflow together, so only one of them fits alongside the third
+        let items = [
+            item(u64::MAX - 1, 1, 5),
+            item(u64::MAX - 1, 1, 4),
+            item(1, u64::MAX - 1, 3),
+        ];
+        let limits = Limits {
+            volume: u64::MAX,
+            weight: u64::MAX,
+        };
+        for plan in [
+            greedy(&items, limits),
+            branch_and_bound(&items, limits, 1000),
+        ] {
+            assert!(feasible(&items, limits, &plan), "{plan:?}");
+            assert_eq!(plan.value, 8);
+        }
+    }
+
+    #[test]
+    fn test_values_near_the_top_of_u64() {
+        // Any two of these are worth more than a u64 can hold
+        let big = u64::MAX / 2 + 1;
+        let items = [item(1, 1, big), item(1, 1, big), item(1, 1, big)];
+        let limits = Limits {
+            volume: 2,
+            weight: 2,
+        };
+        for plan in [
+            dynamic(&items, limits),
+            greedy(&items, limits),
+            branch_and_bound(&items, limits, 1000),
+        ] {
+            assert_eq!(plan.value, 2 * u128::from(big), "{plan:?}");
+        }
+    }
+
+    #[test]
+    fn test_methods_agree_on_random_instances() {
+        let mut rng = XorShift::new(42);
+        for _ in 0..200 {
+            let items: Vec<Item> = (0..rng.between(0..=12))
+                .map(|_| {
+                    item(
+                        rng.between(0..=20),
+                        rng.between(0..=20),
+                        rng.between(0..=30),
+                    )
+                })
+                .collect();
+            let limits = Limits {
+                volume: rng.between(0..=60),
+                weight: rng.between(0..=60),
+            };
+
+            let exact = dynamic(&items, limits);
+            let searched = branch_and_bound(&items, limits, u64::MAX);
+            let quick = greedy(&items, limits);
+
+            assert!(feasible(&items, limits, &exact));
+            assert!(feasible(&items, limits, &searched));
+            assert!(feasible(&items, limits, &quick));
+            assert_eq!(searched.value, exact.value, "{items:?} {limits:?}");
+            assert!(searched.optimal);
+            assert!(quick.value <= exact.value);
+        }
+    }
+
+    #[test]
+    fn test_budget_keeps_a_valid_plan() {
+        let mut rng = XorShift::new(7);
+        let items: Vec<Item> = (0..200)
+            .map(|_| {
+                item(
+                    rng.between(1..=100),
+                    rng.between(1..=100),
+                    rng.between(1..=100),
+                )
+            })
+            .collect();
+        let limits = Limits {
+            volume: 2000,
+            weight: 2000,
+        };
+
+        let plan = branch_and_bound(&items, limits, 50);
+        assert!(!plan.optimal);
+        assert!(feasible(&items, limits, &plan));
+        assert!(plan.value >= greedy(&items, limits).value);
+    }
+}
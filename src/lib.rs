@@ -0,0 +1,17 @@
+//! Building blocks shared between the days, for the puzzles that keep coming back to the same ideas.
+
+#![warn(clippy::pedantic)]
+
+pub mod binpack;
+pub mod bitset;
+pub mod combinatorics;
+pub mod delivery;
+pub mod floors;
+pub mod geometry;
+pub mod graph;
+pub mod grid;
+pub mod image;
+pub mod knapsack;
+pub mod parse;
+pub mod rng;
+pub mod search;
@@ -1,26 +1,91 @@
 #![warn(clippy::pedantic)]
 
+mod history;
+
 use anyhow::{anyhow, Result};
 use std::env;
+use std::path::PathBuf;
 use std::process::Command;
+use std::time::Instant;
+
+/// This crate's manifest, so days build and run the same from any directory.
+const MANIFEST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
-    let day: usize = args
-        .get(1)
-        .ok_or_else(|| anyhow!("No day provided"))?
-        .parse()?;
+    let first = args.get(1).ok_or_else(|| anyhow!("No day provided"))?;
+
+    if first == "history" {
+        let day = args.get(2).map(|day| day.parse()).transpose()?;
+        return history::show(day);
+    }
 
+    // `aoc2015 run 1 --query basement` reads better than `aoc2015 1 --query basement`
+    if first == "run" {
+        let day = args.get(2).ok_or_else(|| anyhow!("No day provided"))?;
+        return run(day.parse()?, &args[3..]);
+    }
+
+    // `aoc2015 render 3 --out routes.svg` is a day that can draw itself, being told to
+    if first == "render" {
+        let day = args.get(2).ok_or_else(|| anyhow!("No day provided"))?;
+        let mut extra = vec!["--render".to_string()];
+        extra.extend_from_slice(&args[3..]);
+        return run(day.parse()?, &extra);
+    }
+
+    run(first.parse()?, &args[2..])
+}
+
+/// Build and run a day. Any `extra` arguments are passed through to the day itself, e.g.
+/// `aoc2015 2 --report csv` or `aoc2015 run 1 --query histogram`, in which case it runs
+/// attached to the terminal and isn't timed or recorded, since it's not solving the puzzle.
+///
+/// Otherwise the whole process is timed, and both parts are recorded with that one
+/// duration, since the days don't time their parts separately.
+fn run(day: usize, extra: &[String]) -> Result<()> {
     // Zero pad
-    let day = format!("{day:02}");
+    let padded = format!("{day:02}");
+    let bin = format!("day{padded}");
 
-    let cmd = Command::new("cargo")
-        .args(["run", "--release", "--bin", &format!("day{day}")])
+    // Build first so compiling doesn't count towards the recorded run time
+    let build = Command::new("cargo")
+        .args([
+            "build",
+            "--release",
+            "--manifest-path",
+            MANIFEST,
+            "--bin",
+            &bin,
+        ])
         .output()?;
 
+    if !build.status.success() {
+        return Err(anyhow!(
+            "Failed to build day {padded}: {}",
+            String::from_utf8(build.stderr)?
+        ));
+    }
+
+    let exe = target_dir()?
+        .join("release")
+        .join(format!("{bin}{}", env::consts::EXE_SUFFIX));
+
+    if !extra.is_empty() {
+        let status = Command::new(exe).args(extra).status()?;
+        if !status.success() {
+            return Err(anyhow!("Failed to run day {padded}: {status}"));
+        }
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    let cmd = Command::new(exe).output()?;
+    let elapsed = start.elapsed();
+
     if !cmd.status.success() {
         return Err(anyhow!(
-            "Failed to run day {day}: {}",
+            "Failed to run day {padded}: {}",
             String::from_utf8(cmd.stderr)?
         ));
     }
@@ -28,8 +93,9 @@ fn main() -> Result<()> {
     let output = String::from_utf8(cmd.stdout)?;
 
     let message = if output.is_empty() {
-        format!("Day {day} not solved!")
+        format!("Day {padded} not solved!")
     } else {
+        history::record(day, &output, elapsed)?;
         output.trim().to_string()
     };
 
@@ -37,3 +103,31 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Where cargo puts the build output, which could be anywhere `CARGO_TARGET_DIR` or a
+/// `.cargo/config.toml` says, so cargo is asked rather than guessing.
+fn target_dir() -> Result<PathBuf> {
+    let metadata = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+            MANIFEST,
+        ])
+        .output()?;
+
+    if !metadata.status.success() {
+        return Err(anyhow!(
+            "Failed to find the target directory: {}",
+            String::from_utf8(metadata.stderr)?
+        ));
+    }
+
+    let metadata = json::parse(&String::from_utf8(metadata.stdout)?)?;
+    metadata["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("cargo metadata didn't give a target directory"))
+}
@@ -0,0 +1,587 @@
+//! A tiny parser combinator toolkit for puzzle input lines.
+//!
+//! Every primitive skips leading whitespace before its token, so a line can be described
+//! token by token without worrying about the spaces in between. Failures remember what
+//! was expected and where, so a bad line reports e.g. ``expected `through` at column 13,
+//! found `thru` `` rather than just "invalid line".
+
+use std::any::type_name;
+use std::fmt;
+use std::str::FromStr;
+
+/// The part of a line still to be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Input<'a> {
+    source: &'a str,
+    offset: usize,
+}
+
+impl<'a> Input<'a> {
+    #[must_use]
+    pub fn new(source: &'a str) -> Input<'a> {
+        Input { source, offset: 0 }
+    }
+
+    /// Everything not yet consumed.
+    #[must_use]
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    /// The byte offset into the original line.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn skip_whitespace(self) -> Input<'a> {
+        let rest = self.rest();
+        self.advance(rest.len() - rest.trim_start().len())
+    }
+
+    fn advance(self, n: usize) -> Input<'a> {
+        Input {
+            source: self.source,
+            offset: self.offset + n,
+        }
+    }
+
+    fn fail<T>(self, expected: Expected) -> PResult<'a, T> {
+        let found = match self.rest().split_whitespace().next() {
+            Some(token) => format!("`{token}`"),
+            None => "end of input".to_string(),
+        };
+        Err(ParseError {
+            expected: vec![expected],
+            offset: self.offset,
+            found,
+        })
+    }
+}
+
+/// A byte range of the original line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A parsed value along with where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+/// A token a parser was looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Literal(&'static str),
+    /// Any one of these characters
+    OneOf(&'static str),
+    /// An integer, of the named type
+    Integer(&'static str),
+    Identifier,
+    End,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Literal(literal) => write!(f, "`{literal}`"),
+            Expected::OneOf(chars) => {
+                let count = chars.chars().count();
+                for (i, c) in chars.chars().enumerate() {
+                    match i {
+                        0 => {}
+                        _ if i == count - 1 => write!(f, " or ")?,
+                        _ => write!(f, ", ")?,
+                    }
+                    write!(f, "`{c}`")?;
+                }
+                Ok(())
+            }
+            Expected::Integer(kind) => write!(f, "an integer ({kind})"),
+            Expected::Identifier => write!(f, "an identifier"),
+            Expected::End => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Everything that would have been accepted here
+    pub expected: Vec<Expected>,
+    /// Byte offset into the line
+    pub offset: usize,
+    /// The token actually there, or "end of input"
+    pub found: String,
+}
+
+impl ParseError {
+    /// Keep whichever error got further, or all the expectations if they got equally far.
+    fn merge(mut self, other: ParseError) -> ParseError {
+        match self.offset.cmp(&other.offset) {
+            std::cmp::Ordering::Less => other,
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Equal => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected ")?;
+        for (i, expected) in self.expected.iter().enumerate() {
+            if i > 0 {
+                let sep = if i == self.expected.len() - 1 {
+                    " or "
+                } else {
+                    ", "
+                };
+                write!(f, "{sep}")?;
+            }
+            write!(f, "{expected}")?;
+        }
+        write!(f, " at column {}, found {}", self.offset + 1, self.found)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type PResult<'a, T> = Result<(T, Input<'a>), ParseError>;
+
+/// Anything that can turn the start of an [`Input`] into a `T`, handing back the rest.
+pub trait Parser<'a, T> {
+    /// # Errors
+    ///
+    /// If the input doesn't start with what the parser expects.
+    fn parse(&self, input: Input<'a>) -> PResult<'a, T>;
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(Input<'a>) -> PResult<'a, T>,
+{
+    fn parse(&self, input: Input<'a>) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Run `parser` over the whole of `line`, which must have nothing but whitespace left over.
+///
+/// # Errors
+///
+/// If `parser` fails, or doesn't consume the whole line.
+pub fn complete<'a, T>(parser: &impl Parser<'a, T>, line: &'a str) -> Result<T, ParseError> {
+    let (value, rest) = parser.parse(Input::new(line))?;
+    let ((), _) = end().parse(rest)?;
+    Ok(value)
+}
+
+/// Whether `b` can be part of an [`identifier`].
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Whether a literal that ends in a word character (`whole_word`) is followed by more of
+/// the word in `after`, so it only matched part of it.
+fn runs_on(whole_word: bool, after: &str) -> bool {
+    whole_word && after.bytes().next().is_some_and(is_word)
+}
+
+/// Exactly `expected`. If it ends in a letter, digit or underscore, the word has to end
+/// there too, so `turn` doesn't match the start of `turnon`.
+#[must_use]
+pub fn literal<'a>(expected: &'static str) -> impl Parser<'a, &'a str> {
+    let whole_word = expected.bytes().last().is_some_and(is_word);
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        match input.rest().strip_prefix(expected) {
+            Some(after) if !runs_on(whole_word, after) => Ok((
+                &input.rest()[..expected.len()],
+                input.advance(expected.len()),
+            )),
+            _ => input.fail(Expected::Literal(expected)),
+        }
+    }
+}
+
+/// `expected` in any mix of upper and lower case, e.g. `turn` or `TURN`. Words have to end
+/// where it does, the same as for [`literal`].
+#[must_use]
+pub fn literal_ignore_case<'a>(expected: &'static str) -> impl Parser<'a, &'a str> {
+    let whole_word = expected.bytes().last().is_some_and(is_word);
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        let rest = input.rest();
+        match rest.get(..expected.len()) {
+            Some(found)
+                if found.eq_ignore_ascii_case(expected)
+                    && !runs_on(whole_word, &rest[expected.len()..]) =>
+            {
+                Ok((found, input.advance(expected.len())))
+            }
+            _ => input.fail(Expected::Literal(expected)),
+        }
+    }
+}
+
+/// Any single character in `chars`. Unlike [`literal`] it doesn't care what follows, so it
+/// suits separators that run straight into the next token, like the `x`s in `2x3x4`.
+#[must_use]
+pub fn one_of<'a>(chars: &'static str) -> impl Parser<'a, char> {
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        match input.rest().chars().next() {
+            Some(c) if chars.contains(c) => Ok((c, input.advance(c.len_utf8()))),
+            _ => input.fail(Expected::OneOf(chars)),
+        }
+    }
+}
+
+/// An optionally negative run of digits that parses as a `T`.
+#[must_use]
+pub fn integer<'a, T: FromStr>() -> impl Parser<'a, T> {
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        let rest = input.rest();
+        let sign = usize::from(rest.starts_with('-'));
+        let digits = rest[sign..].bytes().take_while(u8::is_ascii_digit).count();
+
+        match rest[..sign + digits].parse() {
+            Ok(value) if digits > 0 => Ok((value, input.advance(sign + digits))),
+            _ => input.fail(Expected::Integer(type_name::<T>())),
+        }
+    }
+}
+
+/// A letter or underscore followed by any number of letters, digits and underscores.
+#[must_use]
+pub fn identifier<'a>() -> impl Parser<'a, &'a str> {
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        let rest = input.rest();
+
+        let starts_ok = rest
+            .bytes()
+            .next()
+            .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_');
+        if !starts_ok {
+            return input.fail(Expected::Identifier);
+        }
+
+        let len = rest.bytes().take_while(|&b| is_word(b)).count();
+        Ok((&rest[..len], input.advance(len)))
+    }
+}
+
+/// Nothing but whitespace left.
+#[must_use]
+pub fn end<'a>() -> impl Parser<'a, ()> {
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        if input.rest().is_empty() {
+            Ok(((), input))
+        } else {
+            input.fail(Expected::End)
+        }
+    }
+}
+
+pub fn map<'a, A, B>(parser: impl Parser<'a, A>, f: impl Fn(A) -> B) -> impl Parser<'a, B> {
+    move |input: Input<'a>| {
+        let (value, rest) = parser.parse(input)?;
+        Ok((f(value), rest))
+    }
+}
+
+/// `first` then `second`, keeping only what `second` parsed.
+pub fn preceded<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, B> {
+    move |input: Input<'a>| {
+        let (_, rest) = first.parse(input)?;
+        second.parse(rest)
+    }
+}
+
+/// `first` then `second`, keeping only what `first` parsed.
+pub fn terminated<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, A> {
+    move |input: Input<'a>| {
+        let (value, rest) = first.parse(input)?;
+        let (_, rest) = second.parse(rest)?;
+        Ok((value, rest))
+    }
+}
+
+/// One or more `item`s with a `separator` between each.
+pub fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: Input<'a>| {
+        let (first, mut rest) = item.parse(input)?;
+        let mut items = vec![first];
+        while let Ok((_, after_separator)) = separator.parse(rest) {
+            let (next, after_item) = item.parse(after_separator)?;
+            items.push(next);
+            rest = after_item;
+        }
+        Ok((items, rest))
+    }
+}
+
+/// Wrap what `parser` produces with the span of line it was parsed from.
+pub fn spanned<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Spanned<T>> {
+    move |input: Input<'a>| {
+        let input = input.skip_whitespace();
+        let (value, rest) = parser.parse(input)?;
+        let span = Span {
+            start: input.offset(),
+            end: rest.offset(),
+        };
+        Ok((Spanned { value, span }, rest))
+    }
+}
+
+/// A tuple of parsers to run one after another, see [`seq`].
+pub trait Sequence<'a, T> {
+    /// # Errors
+    ///
+    /// If any parser in the sequence fails.
+    fn parse_sequence(&self, input: Input<'a>) -> PResult<'a, T>;
+}
+
+/// A tuple of parsers to try in turn, see [`alt`].
+pub trait Alternatives<'a, T> {
+    /// # Errors
+    ///
+    /// If every parser fails.
+    fn parse_alternatives(&self, input: Input<'a>) -> PResult<'a, T>;
+}
+
+macro_rules! tuple_combinators {
+    ($($parser:ident $output:ident $value:ident $index:tt),+) => {
+        impl<'a, $($parser, $output),+> Sequence<'a, ($($output,)+)> for ($($parser,)+)
+        where
+            $($parser: Parser<'a, $output>),+
+        {
+            fn parse_sequence(&self, input: Input<'a>) -> PResult<'a, ($($output,)+)> {
+                $(let ($value, input) = self.$index.parse(input)?;)+
+                Ok((($($value,)+), input))
+            }
+        }
+
+        impl<'a, T, $($parser),+> Alternatives<'a, T> for ($($parser,)+)
+        where
+            $($parser: Parser<'a, T>),+
+        {
+            fn parse_alternatives(&self, input: Input<'a>) -> PResult<'a, T> {
+                let mut error: Option<ParseError> = None;
+                $(
+                    match self.$index.parse(input) {
+                        Ok(parsed) => return Ok(parsed),
+                        Err(e) => error = Some(match error {
+                            Some(error) => error.merge(e),
+                            None => e,
+                        }),
+                    }
+                )+
+                Err(error.expect("at least one alternative"))
+            }
+        }
+    };
+}
+
+tuple_combinators!(A TA a 0, B TB b 1);
+tuple_combinators!(A TA a 0, B TB b 1, C TC c 2);
+tuple_combinators!(A TA a 0, B TB b 1, C TC c 2, D TD d 3);
+tuple_combinators!(A TA a 0, B TB b 1, C TC c 2, D TD d 3, E TE e 4);
+tuple_combinators!(A TA a 0, B TB b 1, C TC c 2, D TD d 3, E TE e 4, F TF f 5);
+
+/// Run each parser in the tuple in order, collecting their outputs into a tuple.
+pub fn seq<'a, T, S: Sequence<'a, T>>(parsers: S) -> impl Parser<'a, T> {
+    move |input: Input<'a>| parsers.parse_sequence(input)
+}
+
+/// Try each parser in the tuple in order, returning the first success.
+///
+/// If they all fail, the error comes from whichever got furthest, listing everything
+/// that was expected at that point. The first success wins outright though, so if a later
+/// token fails the other alternatives aren't mentioned; when that matters, make each
+/// alternative cover the rest of the line.
+pub fn alt<'a, T, A: Alternatives<'a, T>>(parsers: A) -> impl Parser<'a, T> {
+    move |input: Input<'a>| parsers.parse_alternatives(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(complete(&literal("turn"), "  turn "), Ok("turn"));
+
+        let err = complete(&literal("turn"), "toggle").unwrap_err();
+        assert_eq!(err.expected, vec![Expected::Literal("turn")]);
+        assert_eq!(
+            err.to_string(),
+            "expected `turn` at column 1, found `toggle`"
+        );
+
+        // Words have to end where the literal does, but symbols can run on
+        let err = complete(&literal("turn"), "turnon").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `turn` at column 1, found `turnon`"
+        );
+        assert!(complete(&literal("to"), "to_").is_err());
+        assert!(complete(&seq((literal("turn"), literal("on"))), "turn on").is_ok());
+        assert_eq!(
+            complete(&seq((literal("->"), identifier())), "->x"),
+            Ok(("->", "x"))
+        );
+    }
+
+    #[test]
+    fn test_literal_ignore_case() {
+        assert_eq!(complete(&literal_ignore_case("turn"), "TuRn"), Ok("TuRn"));
+        assert_eq!(complete(&literal_ignore_case("x"), " X "), Ok("X"));
+
+        let err = complete(&literal_ignore_case("turn"), "tur").unwrap_err();
+        assert_eq!(err.to_string(), "expected `turn` at column 1, found `tur`");
+        // Landing in the middle of a multi-byte character fails rather than panicking
+        assert!(complete(&literal_ignore_case("ab"), "aé").is_err());
+        assert!(complete(&literal_ignore_case("turn"), "TURNON").is_err());
+        assert!(complete(&literal_ignore_case("x"), "x2").is_err());
+    }
+
+    #[test]
+    fn test_one_of() {
+        assert_eq!(complete(&one_of("xX"), " X "), Ok('X'));
+        assert_eq!(
+            complete(&seq((one_of("xX"), integer::<u32>())), "x2"),
+            Ok(('x', 2))
+        );
+
+        let err = complete(&one_of("xX"), "*").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `x` or `X` at column 1, found `*`"
+        );
+        let err = complete(&one_of("+-*"), "/").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `+`, `-` or `*` at column 1, found `/`"
+        );
+        assert!(complete(&one_of("xX"), "").is_err());
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(complete(&integer::<u32>(), "123"), Ok(123));
+        assert_eq!(complete(&integer::<i32>(), "-42"), Ok(-42));
+        assert!(complete(&integer::<u32>(), "-42").is_err());
+        assert!(complete(&integer::<u32>(), "abc").is_err());
+
+        let err = complete(&integer::<u16>(), "70000").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected an integer (u16) at column 1, found `70000`"
+        );
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(complete(&identifier(), "London"), Ok("London"));
+        assert_eq!(complete(&identifier(), "snake_case2"), Ok("snake_case2"));
+        assert!(complete(&identifier(), "2fast").is_err());
+    }
+
+    #[test]
+    fn test_end() {
+        let err = complete(&literal("a"), "a b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected end of input at column 3, found `b`"
+        );
+    }
+
+    #[test]
+    fn test_seq_and_map() {
+        let point = map(
+            seq((integer::<u32>(), literal(","), integer::<u32>())),
+            |(x, _, y)| (x, y),
+        );
+        assert_eq!(complete(&point, "660,55"), Ok((660, 55)));
+
+        let err = complete(&point, "660;55").unwrap_err();
+        assert_eq!(err.to_string(), "expected `,` at column 4, found `;55`");
+
+        let err = complete(&point, "660,").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected an integer (u32) at column 5, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_preceded_and_terminated() {
+        let parser = preceded(literal("NOT"), terminated(identifier(), literal(";")));
+        assert_eq!(complete(&parser, "NOT x;"), Ok("x"));
+    }
+
+    #[test]
+    fn test_alt_reports_every_expectation() {
+        let op = alt((literal("AND"), literal("OR"), literal("LSHIFT")));
+        assert_eq!(complete(&op, "OR"), Ok("OR"));
+
+        let err = complete(&op, "XOR").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `AND`, `OR` or `LSHIFT` at column 1, found `XOR`"
+        );
+    }
+
+    #[test]
+    fn test_alt_keeps_the_furthest_error() {
+        let parser = alt((
+            map(seq((literal("turn"), literal("on"))), |_| 1),
+            map(literal("toggle"), |_| 2),
+        ));
+        let err = complete(&parser, "turn up").unwrap_err();
+        assert_eq!(err.to_string(), "expected `on` at column 6, found `up`");
+    }
+
+    #[test]
+    fn test_separated() {
+        // Present dimensions run `x` straight into digits, which `literal` takes for a
+        // longer word, so they're split with `one_of`
+        let list = separated(integer::<u32>(), one_of("xX"));
+        assert_eq!(complete(&list, "2x3x4"), Ok(vec![2, 3, 4]));
+        assert!(complete(&separated(integer::<u32>(), literal("x")), "2x3x4").is_err());
+        assert_eq!(complete(&list, "7"), Ok(vec![7]));
+        assert!(complete(&list, "2x").is_err());
+    }
+
+    #[test]
+    fn test_spanned() {
+        let parser = seq((spanned(identifier()), literal("to"), spanned(identifier())));
+        let (from, _, to) = complete(&parser, "London to  Dublin").unwrap();
+        assert_eq!(from.span, Span { start: 0, end: 6 });
+        assert_eq!(to.value, "Dublin");
+        assert_eq!(to.span, Span { start: 11, end: 17 });
+    }
+}
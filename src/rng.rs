@@ -0,0 +1,137 @@
+//! A small seeded random number generator, for property tests and generated inputs.
+//!
+//! This is Marsaglia's xorshift64: fast, reproducible from a seed, and nowhere near good
+//! enough for anything that needs real randomness.
+
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// A generator that always produces the same sequence for the same `seed`.
+    #[must_use]
+    pub fn new(seed: u64) -> XorShift {
+        // Zero is the one state xorshift can never leave
+        XorShift {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..bound`, without the bias plain `%` has.
+    ///
+    /// # Panics
+    ///
+    /// If `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Can't pick a number below 0");
+        // Throw away the top partial run of values so every remainder is equally likely
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// A number in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty.
+    pub fn between(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (low, high) = range.into_inner();
+        assert!(low <= high, "Can't pick a number between {low} and {high}");
+        match (high - low).checked_add(1) {
+            Some(span) => low + self.below(span),
+            None => self.next_u64(),
+        }
+    }
+
+    /// `true` with probability `numerator / denominator`.
+    ///
+    /// # Panics
+    ///
+    /// If `denominator` is zero.
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    /// One of `items`, or `None` if there aren't any.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        let index = self.below(items.len() as u64);
+        items.get(usize::try_from(index).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = XorShift::new(42);
+        let mut b = XorShift::new(42);
+        let mut c = XorShift::new(43);
+        let first: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..10).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..10).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_zero_seed() {
+        let mut rng = XorShift::new(0);
+        assert_ne!(rng.next_u64(), 0);
+    }
+
+    #[test]
+    fn test_below_covers_the_range() {
+        let mut rng = XorShift::new(1);
+        let mut counts = [0; 6];
+        for _ in 0..6000 {
+            counts[usize::try_from(rng.below(6)).unwrap()] += 1;
+        }
+        // Each face should come up about 1000 times
+        assert!(
+            counts.iter().all(|&n| (850..1150).contains(&n)),
+            "{counts:?}"
+        );
+    }
+
+    #[test]
+    fn test_between() {
+        let mut rng = XorShift::new(7);
+        for _ in 0..1000 {
+            assert!((10..=12).contains(&rng.between(10..=12)));
+        }
+        assert_eq!(rng.between(5..=5), 5);
+        // The whole range can't be counted in a u64, but still works
+        rng.between(0..=u64::MAX);
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut rng = XorShift::new(3);
+        assert_eq!(rng.pick::<u8>(&[]), None);
+        assert_eq!(rng.pick(&['a']), Some(&'a'));
+        assert!(!rng.chance(0, 10));
+        assert!(rng.chance(10, 10));
+    }
+}
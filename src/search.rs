@@ -0,0 +1,562 @@
+//! Generic shortest path searches over any state type.
+//!
+//! The caller describes the problem with closures: the states reachable from a state,
+//! the cost of a step between two states, and when a state is the goal. A* and IDA* also
+//! take a heuristic, which must never overestimate the remaining cost for the answer to be
+//! the cheapest. Every search reports how much work it did in [`Stats`], so heuristics and
+//! visited-set strategies can be compared.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// How a search avoids exploring the same state twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visited {
+    /// Remember every state reached, never expanding one twice. The usual choice.
+    Graph,
+    /// Only refuse to revisit states on the path being explored. Memory stays
+    /// proportional to the path, at the cost of re-exploring states reached another way.
+    Path,
+    /// Never check. Only safe when the states can't loop back on themselves.
+    Tree,
+}
+
+/// How much work a search did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// States whose neighbours were generated
+    pub expanded: usize,
+    /// Neighbours added to the frontier
+    pub generated: usize,
+    /// The most states waiting on the frontier at once
+    pub max_frontier: usize,
+}
+
+/// The route a search found from the start state to a goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    /// Every state along the way, start and goal included
+    pub states: Vec<S>,
+    pub cost: C,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome<S, C> {
+    /// `None` if no goal could be reached
+    pub path: Option<Path<S, C>>,
+    pub stats: Stats,
+}
+
+/// A state the search has reached, and how it got there.
+struct Node<S, C> {
+    state: S,
+    parent: Option<usize>,
+    cost: C,
+}
+
+/// Every node reached so far, so paths can be rebuilt by following parents.
+struct Arena<S, C> {
+    nodes: Vec<Node<S, C>>,
+}
+
+impl<S: Clone + Eq, C: Copy> Arena<S, C> {
+    fn push(&mut self, state: S, parent: Option<usize>, cost: C) -> usize {
+        self.nodes.push(Node {
+            state,
+            parent,
+            cost,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = &Node<S, C>> {
+        std::iter::successors(Some(&self.nodes[index]), |node| {
+            node.parent.map(|parent| &self.nodes[parent])
+        })
+    }
+
+    fn on_path(&self, index: usize, state: &S) -> bool {
+        self.ancestors(index).any(|node| node.state == *state)
+    }
+
+    fn path(&self, index: usize) -> Path<S, C> {
+        let mut states: Vec<S> = self.ancestors(index).map(|n| n.state.clone()).collect();
+        states.reverse();
+        Path {
+            states,
+            cost: self.nodes[index].cost,
+        }
+    }
+}
+
+/// Breadth first search, the path with the fewest steps to a goal.
+pub fn bfs<S, N, I>(
+    start: S,
+    mut neighbours: N,
+    is_goal: impl Fn(&S) -> bool,
+    visited: Visited,
+) -> Outcome<S, usize>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut stats = Stats::default();
+    let mut arena = Arena { nodes: Vec::new() };
+    let mut seen: HashSet<S> = HashSet::new();
+    let mut frontier = VecDeque::new();
+
+    if visited == Visited::Graph {
+        seen.insert(start.clone());
+    }
+    frontier.push_back(arena.push(start, None, 0));
+
+    while let Some(index) = frontier.pop_front() {
+        if is_goal(&arena.nodes[index].state) {
+            return Outcome {
+                path: Some(arena.path(index)),
+                stats,
+            };
+        }
+
+        stats.expanded += 1;
+        let steps = arena.nodes[index].cost + 1;
+        for next in neighbours(&arena.nodes[index].state) {
+            let fresh = match visited {
+                Visited::Graph => seen.insert(next.clone()),
+                Visited::Path => !arena.on_path(index, &next),
+                Visited::Tree => true,
+            };
+            if fresh {
+                stats.generated += 1;
+                frontier.push_back(arena.push(next, Some(index), steps));
+            }
+        }
+        stats.max_frontier = stats.max_frontier.max(frontier.len());
+    }
+
+    Outcome { path: None, stats }
+}
+
+/// Dijkstra's algorithm, the cheapest path to a goal when every step costs zero or more.
+pub fn dijkstra<S, C, N, I>(
+    start: S,
+    neighbours: N,
+    cost: impl Fn(&S, &S) -> C,
+    is_goal: impl Fn(&S) -> bool,
+    visited: Visited,
+) -> Outcome<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    astar(start, neighbours, cost, |_| C::default(), is_goal, visited)
+}
+
+/// A*, Dijkstra's algorithm guided towards the goal by `heuristic`.
+pub fn astar<S, C, N, I>(
+    start: S,
+    mut neighbours: N,
+    cost: impl Fn(&S, &S) -> C,
+    heuristic: impl Fn(&S) -> C,
+    is_goal: impl Fn(&S) -> bool,
+    visited: Visited,
+) -> Outcome<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Add<Output = C> + Default,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut stats = Stats::default();
+    let mut arena = Arena { nodes: Vec::new() };
+    // The cheapest cost found to each state so far, with Visited::Graph
+    let mut best: HashMap<S, C> = HashMap::new();
+    // Ties on estimated total go to whichever got further, then to whichever was queued
+    // first, so a good heuristic heads straight for the goal and equal paths come out stably
+    let mut frontier: BinaryHeap<Reverse<(C, Reverse<C>, usize)>> = BinaryHeap::new();
+
+    if visited == Visited::Graph {
+        best.insert(start.clone(), C::default());
+    }
+    let estimate = heuristic(&start);
+    let index = arena.push(start, None, C::default());
+    frontier.push(Reverse((estimate, Reverse(C::default()), index)));
+
+    while let Some(Reverse((_, _, index))) = frontier.pop() {
+        let node = &arena.nodes[index];
+        if visited == Visited::Graph && best.get(&node.state).is_some_and(|&b| b < node.cost) {
+            // A cheaper way here was found after this one was queued
+            continue;
+        }
+        if is_goal(&node.state) {
+            return Outcome {
+                path: Some(arena.path(index)),
+                stats,
+            };
+        }
+
+        stats.expanded += 1;
+        let current = arena.nodes[index].state.clone();
+        let so_far = arena.nodes[index].cost;
+        for next in neighbours(&current) {
+            let total = so_far + cost(&current, &next);
+            let fresh = match visited {
+                Visited::Graph => {
+                    if best.get(&next).is_some_and(|&b| b <= total) {
+                        false
+                    } else {
+                        best.insert(next.clone(), total);
+                        true
+                    }
+                }
+                Visited::Path => !arena.on_path(index, &next),
+                Visited::Tree => true,
+            };
+            if fresh {
+                stats.generated += 1;
+                let estimate = total + heuristic(&next);
+                let index = arena.push(next, Some(index), total);
+                frontier.push(Reverse((estimate, Reverse(total), index)));
+            }
+        }
+        stats.max_frontier = stats.max_frontier.max(frontier.len());
+    }
+
+    Outcome { path: None, stats }
+}
+
+/// Iterative deepening A*: depth first searches with a growing bound on the estimated
+/// total cost.
+///
+/// Only the current path is kept in memory, which is also what's checked for repeated
+/// states, so it suits state spaces too big for [`astar`] to remember. A state space
+/// with no goal and no end never finishes.
+pub fn idastar<S, C, N, I>(
+    start: S,
+    mut neighbours: N,
+    cost: impl Fn(&S, &S) -> C,
+    heuristic: impl Fn(&S) -> C,
+    is_goal: impl Fn(&S) -> bool,
+) -> Outcome<S, C>
+where
+    S: Clone + Eq,
+    C: Copy + Ord + Add<Output = C> + Default,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut search = Deepening {
+        neighbours: &mut neighbours,
+        cost: &cost,
+        heuristic: &heuristic,
+        is_goal: &is_goal,
+        path: vec![start],
+        stats: Stats::default(),
+    };
+
+    let mut bound = heuristic(&search.path[0]);
+    loop {
+        match search.explore(C::default(), bound) {
+            Deepened::Found(cost) => {
+                return Outcome {
+                    path: Some(Path {
+                        states: search.path,
+                        cost,
+                    }),
+                    stats: search.stats,
+                }
+            }
+            Deepened::Exceeded(next) => bound = next,
+            Deepened::Exhausted => {
+                return Outcome {
+                    path: None,
+                    stats: search.stats,
+                }
+            }
+        }
+    }
+}
+
+enum Deepened<C> {
+    Found(C),
+    /// The smallest estimated total that went over the bound
+    Exceeded(C),
+    Exhausted,
+}
+
+struct Deepening<'a, S, N, F, H, G> {
+    neighbours: &'a mut N,
+    cost: &'a F,
+    heuristic: &'a H,
+    is_goal: &'a G,
+    path: Vec<S>,
+    stats: Stats,
+}
+
+impl<S, C, N, I, F, H, G> Deepening<'_, S, N, F, H, G>
+where
+    S: Clone + Eq,
+    C: Copy + Ord + Add<Output = C> + Default,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    F: Fn(&S, &S) -> C,
+    H: Fn(&S) -> C,
+    G: Fn(&S) -> bool,
+{
+    fn explore(&mut self, so_far: C, bound: C) -> Deepened<C> {
+        let current = self.path[self.path.len() - 1].clone();
+        let estimate = so_far + (self.heuristic)(&current);
+        if estimate > bound {
+            return Deepened::Exceeded(estimate);
+        }
+        if (self.is_goal)(&current) {
+            return Deepened::Found(so_far);
+        }
+
+        self.stats.expanded += 1;
+        let mut smallest_over: Option<C> = None;
+        let options: Vec<S> = (self.neighbours)(&current).into_iter().collect();
+        for next in options {
+            if self.path.contains(&next) {
+                continue;
+            }
+            self.stats.generated += 1;
+            let total = so_far + (self.cost)(&current, &next);
+            self.path.push(next);
+            self.stats.max_frontier = self.stats.max_frontier.max(self.path.len());
+
+            match self.explore(total, bound) {
+                Deepened::Found(cost) => return Deepened::Found(cost),
+                Deepened::Exceeded(over) => {
+                    smallest_over = Some(smallest_over.map_or(over, |s| s.min(over)));
+                }
+                Deepened::Exhausted => {}
+            }
+            self.path.pop();
+        }
+
+        smallest_over.map_or(Deepened::Exhausted, Deepened::Exceeded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{Graph, NodeId};
+    use crate::parse::{complete, identifier, integer, literal, seq};
+
+    const DAY09_INPUT: &str = include_str!("inputs/day09.txt");
+
+    /// A small weighted graph where the fewest hops and the cheapest route differ.
+    ///
+    /// ```text
+    /// a -1- b -1- c -1- f
+    ///  \               /
+    ///   ------ 9 ------
+    /// ```
+    fn diamond() -> Graph<char, u32> {
+        let mut graph = Graph::undirected();
+        graph.add_edge('a', 'b', 1);
+        graph.add_edge('b', 'c', 1);
+        graph.add_edge('c', 'f', 1);
+        graph.add_edge('a', 'f', 9);
+        graph.intern('x'); // unreachable
+        graph
+    }
+
+    fn names(graph: &Graph<char, u32>, path: &Path<NodeId, impl Copy>) -> String {
+        path.states.iter().map(|&id| *graph.name(id)).collect()
+    }
+
+    #[test]
+    fn test_bfs_fewest_steps() {
+        let graph = diamond();
+        let (a, f) = (graph.id(&'a').unwrap(), graph.id(&'f').unwrap());
+
+        for visited in [Visited::Graph, Visited::Path] {
+            let outcome = bfs(
+                a,
+                |&n| graph.neighbours(n).map(|(to, _)| to),
+                |&n| n == f,
+                visited,
+            );
+            let path = outcome.path.unwrap();
+            assert_eq!(names(&graph, &path), "af");
+            assert_eq!(path.cost, 1);
+        }
+    }
+
+    #[test]
+    fn test_dijkstra_cheapest() {
+        let graph = diamond();
+        let (a, f) = (graph.id(&'a').unwrap(), graph.id(&'f').unwrap());
+
+        for visited in [Visited::Graph, Visited::Path] {
+            let outcome = dijkstra(
+                a,
+                |&n| graph.neighbours(n).map(|(to, _)| to),
+                |&from, &to| graph.weight(from, to).unwrap(),
+                |&n| n == f,
+                visited,
+            );
+            let path = outcome.path.unwrap();
+            assert_eq!(names(&graph, &path), "abcf");
+            assert_eq!(path.cost, 3);
+        }
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let graph = diamond();
+        let (a, x) = (graph.id(&'a').unwrap(), graph.id(&'x').unwrap());
+
+        let outcome = bfs(
+            a,
+            |&n| graph.neighbours(n).map(|(to, _)| to),
+            |&n| n == x,
+            Visited::Graph,
+        );
+        assert_eq!(outcome.path, None);
+        assert_eq!(outcome.stats.expanded, 4);
+
+        let outcome = idastar(
+            a,
+            |&n| graph.neighbours(n).map(|(to, _)| to),
+            |&from, &to| graph.weight(from, to).unwrap(),
+            |_| 0,
+            |&n| n == x,
+        );
+        assert_eq!(outcome.path, None);
+    }
+
+    #[test]
+    fn test_astar_on_a_grid() {
+        // Manhattan distance across an open 20x20 grid, A* should go straight there
+        let goal = (19, 19);
+        let neighbours = |&(x, y): &(i32, i32)| {
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..20).contains(&x) && (0..20).contains(&y))
+        };
+        let manhattan = |&(x, y): &(i32, i32)| (goal.0 - x).abs() + (goal.1 - y).abs();
+
+        let guided = astar(
+            (0, 0),
+            neighbours,
+            |_, _| 1,
+            manhattan,
+            |&p| p == goal,
+            Visited::Graph,
+        );
+        let blind = dijkstra((0, 0), neighbours, |_, _| 1, |&p| p == goal, Visited::Graph);
+
+        assert_eq!(guided.path.as_ref().unwrap().cost, 38);
+        assert_eq!(blind.path.as_ref().unwrap().cost, 38);
+        assert_eq!(guided.path.unwrap().states.len(), 39);
+        assert!(guided.stats.expanded < blind.stats.expanded);
+    }
+
+    #[test]
+    fn test_tree_search() {
+        // Counting up by 1 or 3 can't loop, so there's no need to remember anything
+        let outcome = bfs(0, |&n| [n + 1, n + 3], |&n| n == 10, Visited::Tree);
+        let path = outcome.path.unwrap();
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.states, vec![0, 1, 4, 7, 10]);
+    }
+
+    /// Day 9 as a search: the state is where Santa is and everywhere he's been, and the
+    /// goal is having been everywhere.
+    type Route = (Option<NodeId>, u64);
+
+    fn day09(input: &str) -> Graph<&str, u32> {
+        let leg = seq((
+            identifier(),
+            literal("to"),
+            identifier(),
+            literal("="),
+            integer(),
+        ));
+        let mut distances = Graph::undirected();
+        for line in input.trim().lines() {
+            let (from, _, to, _, distance) = complete(&leg, line).unwrap();
+            distances.add_edge(from, to, distance);
+        }
+        distances
+    }
+
+    fn shortest_route(
+        distances: &Graph<&str, u32>,
+    ) -> (
+        Outcome<Route, u32>,
+        Outcome<Route, u32>,
+        Outcome<Route, u32>,
+    ) {
+        let everywhere = (1u64 << distances.len()) - 1;
+        let cheapest_leg = distances.edges().map(|(_, _, d)| d).min().unwrap();
+
+        let neighbours = |&(at, been): &Route| {
+            distances
+                .nodes()
+                .filter(move |&next| been & (1 << next) == 0)
+                .filter(move |&next| at.is_none_or(|at| distances.weight(at, next).is_some()))
+                .map(move |next| (Some(next), been | 1 << next))
+        };
+        let cost = |&(from, _): &Route, &(to, _): &Route| match (from, to) {
+            (Some(from), Some(to)) => distances.weight(from, to).unwrap(),
+            _ => 0,
+        };
+        // Every place left needs at least one leg to get to it
+        let heuristic = |&(at, been): &Route| {
+            let left = (everywhere & !been).count_ones();
+            let legs = if at.is_some() {
+                left
+            } else {
+                left.saturating_sub(1)
+            };
+            legs * cheapest_leg
+        };
+        let is_goal = |&(_, been): &Route| been == everywhere;
+
+        (
+            dijkstra((None, 0), neighbours, cost, is_goal, Visited::Graph),
+            astar(
+                (None, 0),
+                neighbours,
+                cost,
+                heuristic,
+                is_goal,
+                Visited::Graph,
+            ),
+            idastar((None, 0), neighbours, cost, heuristic, is_goal),
+        )
+    }
+
+    #[test]
+    fn test_day09_example() {
+        let distances =
+            day09("London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141");
+        let (dijkstra, astar, idastar) = shortest_route(&distances);
+        for outcome in [dijkstra, astar, idastar] {
+            let path = outcome.path.unwrap();
+            assert_eq!(path.cost, 605);
+            // The empty start, then one state per city
+            assert_eq!(path.states.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_day09_distance_table() {
+        let distances = day09(DAY09_INPUT);
+        let (dijkstra, astar, idastar) = shortest_route(&distances);
+
+        assert_eq!(dijkstra.path.unwrap().cost, 251);
+        assert_eq!(astar.path.unwrap().cost, 251);
+        assert_eq!(idastar.path.unwrap().cost, 251);
+        assert!(astar.stats.expanded <= dijkstra.stats.expanded);
+    }
+}